@@ -0,0 +1,105 @@
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb {
+    pub minimum: Point3,
+    pub maximum: Point3,
+}
+
+impl Aabb {
+    pub fn new(minimum: Point3, maximum: Point3) -> Aabb {
+        Aabb { minimum, maximum }
+    }
+
+    pub fn hit(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+        for a in 0..3 {
+            let inverse_dir = 1.0 / r.direction[a];
+            let mut t0 = (self.minimum[a] - r.origin[a]) * inverse_dir;
+            let mut t1 = (self.maximum[a] - r.origin[a]) * inverse_dir;
+            if inverse_dir < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // f64::max/min ignore NaN, which shows up when the origin lies on a slab
+            // boundary and the direction is parallel to it.
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+            if t_max < t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn surrounding_box(box0: Aabb, box1: Aabb) -> Aabb {
+        let small = Point3::new(
+            box0.minimum.x.min(box1.minimum.x),
+            box0.minimum.y.min(box1.minimum.y),
+            box0.minimum.z.min(box1.minimum.z),
+        );
+        let big = Point3::new(
+            box0.maximum.x.max(box1.maximum.x),
+            box0.maximum.y.max(box1.maximum.y),
+            box0.maximum.z.max(box1.maximum.z),
+        );
+
+        Aabb::new(small, big)
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        self.maximum - self.minimum
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        (self.minimum + self.maximum) * 0.5
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.extent();
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::Aabb;
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn hit_aabb() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+
+        assert!(aabb.hit(&ray, 0.001, f64::INFINITY));
+        assert!(!aabb.hit(&ray, 0.001, 3.0));
+    }
+
+    #[test]
+    fn miss_aabb() {
+        let aabb = Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0));
+        let ray = Ray::new(Vec3::new(0.0, 2.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+
+        assert!(!aabb.hit(&ray, 0.001, f64::INFINITY));
+    }
+
+    #[test]
+    fn surrounding_box() {
+        let box0 = Aabb::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0));
+        let box1 = Aabb::new(Vec3::new(0.0, -2.0, 0.0), Vec3::new(0.5, 0.5, 3.0));
+        let result = Aabb::surrounding_box(box0, box1);
+
+        assert_eq!(result.minimum, Vec3::new(-1.0, -2.0, 0.0));
+        assert_eq!(result.maximum, Vec3::new(1.0, 1.0, 3.0));
+    }
+
+    #[test]
+    fn surface_area() {
+        let aabb = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 3.0));
+
+        assert_eq!(aabb.surface_area(), 22.0);
+        assert_eq!(aabb.centroid(), Vec3::new(0.5, 1.0, 1.5));
+    }
+}
@@ -0,0 +1,308 @@
+use std::f64::consts::PI;
+use std::path::Path;
+
+use image::ImageResult;
+
+use crate::distribution::Distribution2D;
+use crate::texture::load_linear;
+use crate::vec3::{Color, Vec3};
+
+/// Radiance seen by rays that leave the scene.
+#[derive(Debug, Clone)]
+pub enum Background {
+    Solid(Color),
+    /// Blends from `bottom` straight down to `top` straight up.
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    Environment(EnvironmentMap),
+}
+
+impl Default for Background {
+    /// The white to sky blue gradient from the book.
+    fn default() -> Background {
+        Background::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Background {
+    pub fn color(&self, direction: Vec3) -> Color {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let t = 0.5 * (direction.unit_vector().y + 1.0);
+                *bottom * (1.0 - t) + *top * t
+            }
+            Background::Environment(map) => map.color(direction),
+        }
+    }
+}
+
+/// Equirectangular (latitude-longitude) radiance map with +y up. The top row is
+/// straight up, the horizontal center of the image looks down -z and +x is a
+/// quarter of the width to the right of it.
+///
+/// Directions can be importance sampled in proportion to the map's luminance, so the
+/// map can be used as a light source.
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    distribution: Distribution2D,
+    /// Turns the map around the vertical axis, in degrees.
+    pub rotation: f64,
+    /// Scales every pixel of the map.
+    pub intensity: f64,
+}
+
+impl EnvironmentMap {
+    /// Creates a map from linear radiance in rows from top to bottom.
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> EnvironmentMap {
+        assert_eq!(
+            pixels.len(),
+            width * height,
+            "environment map size mismatch"
+        );
+
+        // Rows near the poles cover less solid angle, so weight them by sin(theta).
+        let weights: Vec<f64> = pixels
+            .iter()
+            .enumerate()
+            .map(|(index, &pixel)| {
+                let theta = PI * ((index / width) as f64 + 0.5) / height as f64;
+                luminance(pixel) * theta.sin()
+            })
+            .collect();
+
+        EnvironmentMap {
+            width,
+            height,
+            distribution: Distribution2D::new(&weights, width, height),
+            pixels,
+            rotation: 0.0,
+            intensity: 1.0,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Linear radiance in rows from top to bottom, before `intensity` is applied.
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// Loads a Radiance `.hdr`, an OpenEXR or any 8-bit format the `image` crate
+    /// reads. 8-bit images are assumed to be sRGB encoded.
+    pub fn load(path: impl AsRef<Path>) -> ImageResult<EnvironmentMap> {
+        let (width, height, pixels) = load_linear(path)?;
+        Ok(EnvironmentMap::new(width, height, pixels))
+    }
+
+    pub fn color(&self, direction: Vec3) -> Color {
+        let (u, v) = self.direction_to_uv(direction);
+        self.pixel(u, v) * self.intensity
+    }
+
+    /// Unscaled pixel covering the map coordinates `u` and `v`, both in `0..1`.
+    pub fn pixel(&self, u: f64, v: f64) -> Color {
+        let x = ((u * self.width as f64) as usize).min(self.width - 1);
+        let y = ((v * self.height as f64) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x]
+    }
+
+    /// Picks a direction with probability roughly proportional to its radiance.
+    /// Returns the unit direction, the radiance seen along it and the density with
+    /// respect to solid angle.
+    pub fn sample(&self, u1: f64, u2: f64) -> Option<(Vec3, Color, f64)> {
+        let ((u, v), uv_pdf) = self.distribution.sample(u1, u2);
+        let sin_theta = (v * PI).sin();
+        if uv_pdf == 0.0 || sin_theta <= 0.0 {
+            return None;
+        }
+
+        let direction = self.uv_to_direction(u, v);
+        let pdf = uv_pdf / (2.0 * PI * PI * sin_theta);
+        Some((direction, self.pixel(u, v) * self.intensity, pdf))
+    }
+
+    /// Solid angle density of `sample` picking `direction`.
+    pub fn pdf(&self, direction: Vec3) -> f64 {
+        let (u, v) = self.direction_to_uv(direction);
+        let sin_theta = (v * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+
+    /// Map coordinates of a direction, `u` across and `v` down the image.
+    pub fn direction_to_uv(&self, direction: Vec3) -> (f64, f64) {
+        let d = direction.unit_vector();
+        let theta = d.y.clamp(-1.0, 1.0).acos();
+        let phi = d.x.atan2(-d.z) - self.rotation.to_radians();
+        ((0.5 + phi / (2.0 * PI)).rem_euclid(1.0), theta / PI)
+    }
+
+    /// Unit direction for map coordinates, the inverse of `direction_to_uv`.
+    pub fn uv_to_direction(&self, u: f64, v: f64) -> Vec3 {
+        let phi = (u - 0.5) * 2.0 * PI + self.rotation.to_radians();
+        let theta = v * PI;
+        Vec3::new(
+            theta.sin() * phi.sin(),
+            theta.cos(),
+            -theta.sin() * phi.cos(),
+        )
+    }
+}
+
+fn luminance(color: Color) -> f64 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::background::{Background, EnvironmentMap};
+    use crate::render::{Image, OutputFormat};
+    use crate::tonemap::ToneMapping;
+    use crate::vec3::{Color, Vec3};
+
+    #[test]
+    fn solid_and_gradient() {
+        let solid = Background::Solid(Color::new(0.1, 0.2, 0.3));
+        assert_eq!(
+            solid.color(Vec3::new(1.0, 2.0, 3.0)),
+            Color::new(0.1, 0.2, 0.3)
+        );
+
+        let gradient = Background::Gradient {
+            bottom: Color::new(0.0, 0.0, 0.0),
+            top: Color::new(1.0, 0.5, 0.0),
+        };
+        assert_eq!(
+            gradient.color(Vec3::new(0.0, 5.0, 0.0)),
+            Color::new(1.0, 0.5, 0.0)
+        );
+        assert_eq!(
+            gradient.color(Vec3::new(0.0, -5.0, 0.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            gradient.color(Vec3::new(1.0, 0.0, 0.0)),
+            Color::new(0.5, 0.25, 0.0)
+        );
+    }
+
+    /// A 4x2 map whose pixels encode their own index.
+    fn indexed_map() -> EnvironmentMap {
+        EnvironmentMap::new(
+            4,
+            2,
+            (0..8).map(|i| Color::new(i as f64, 0.0, 0.0)).collect(),
+        )
+    }
+
+    #[test]
+    fn environment_lookup() {
+        let mut map = indexed_map();
+        let forward = Vec3::new(0.0, 0.1, -1.0);
+        let up_right = Vec3::new(1.0, 0.1, 0.0);
+        let down_left = Vec3::new(-1.0, -0.1, 0.0);
+
+        // Columns run from +z on the left through -x, -z and +x.
+        assert_eq!(map.color(forward).x, 2.0);
+        assert_eq!(map.color(up_right).x, 3.0);
+        assert_eq!(map.color(down_left).x, 5.0);
+
+        map.rotation = 90.0;
+        map.intensity = 2.0;
+        assert_eq!(map.color(forward).x, 2.0);
+        assert_eq!(Background::Environment(map).color(up_right).x, 4.0);
+    }
+
+    #[test]
+    fn load_hdr() {
+        let image = Image {
+            width: 2,
+            height: 1,
+            pixels: vec![Color::new(4.0, 2.0, 1.0), Color::new(0.5, 0.25, 0.125)],
+        };
+        let path =
+            std::env::temp_dir().join(format!("raytracing_{}_load_hdr.hdr", std::process::id()));
+        image
+            .save(&path, OutputFormat::Hdr, &ToneMapping::default())
+            .unwrap();
+
+        let map = EnvironmentMap::load(&path).unwrap();
+        assert_eq!((map.width(), map.height()), (2, 1));
+        assert_eq!(map.pixels(), image.pixels);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn importance_sampling() {
+        // A dim sky with one bright "sun" pixel.
+        let (width, height) = (16, 8);
+        let mut pixels = vec![Color::new(0.1, 0.1, 0.1); width * height];
+        pixels[2 * width + 5] = Color::new(500.0, 450.0, 400.0);
+        let mut map = EnvironmentMap::new(width, height, pixels);
+        map.rotation = 20.0;
+        map.intensity = 2.0;
+
+        // Radiance integrated over the sphere, pixel by pixel.
+        let mut expected = Color::new(0.0, 0.0, 0.0);
+        for (index, &pixel) in map.pixels().iter().enumerate() {
+            let row = (index / width) as f64;
+            let band = (PI * row / height as f64).cos() - (PI * (row + 1.0) / height as f64).cos();
+            expected += pixel * (map.intensity * 2.0 * PI * band / width as f64);
+        }
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let samples = 20_000;
+        let mut estimate = Color::new(0.0, 0.0, 0.0);
+        let mut sun_samples = 0;
+        for _ in 0..samples {
+            let (direction, radiance, pdf) = map.sample(rng.gen(), rng.gen()).unwrap();
+            assert!((map.pdf(direction) - pdf).abs() < 1e-9 * pdf);
+            estimate += radiance / (pdf * samples as f64);
+            if radiance.x > 100.0 {
+                sun_samples += 1;
+            }
+        }
+
+        assert!((estimate.x - expected.x).abs() < 0.02 * expected.x);
+        assert!(sun_samples > samples * 9 / 10);
+    }
+
+    #[test]
+    fn uv_round_trip() {
+        let mut map = indexed_map();
+        map.rotation = 30.0;
+
+        for direction in [
+            Vec3::new(0.3, 0.5, -0.8),
+            Vec3::new(-0.9, -0.1, 0.2),
+            Vec3::new(0.0, 0.2, 1.0),
+        ] {
+            let (u, v) = map.direction_to_uv(direction);
+            let back = map.uv_to_direction(u, v);
+            assert!((back - direction.unit_vector()).near_zero());
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use crate::hittable::HitRecord;
+use crate::onb::Onb;
+use crate::texture::Texture;
+use crate::vec3::Color;
+
+/// Step in `u` and `v` for the finite differences of height maps.
+const DELTA: f64 = 1e-3;
+
+/// Surface detail that tilts the shading normal without changing the shape.
+#[derive(Debug, Clone)]
+pub enum Bump {
+    /// Tangent space normals stored as colors, the way most tools export them: red
+    /// along `u`, green along `v` and blue out of the surface. `strength` scales the
+    /// tilt.
+    Normal {
+        map: Arc<dyn Texture>,
+        strength: f64,
+    },
+    /// Heights, averaged over the color channels, whose slopes tilt the normal.
+    /// `scale` turns texture values into distances.
+    Height { map: Arc<dyn Texture>, scale: f64 },
+}
+
+impl Bump {
+    /// Replaces the shading normal of `rec`, leaving its geometric normal alone.
+    pub fn apply(&self, rec: &mut HitRecord) {
+        // Work on the outside of the surface so tangent space keeps its handedness
+        // when seen from behind.
+        let flip = if rec.front_face { 1.0 } else { -1.0 };
+        let normal = flip * rec.normal;
+        let (tangent, bitangent) = if rec.tangent.cross(rec.bitangent).near_zero() {
+            let onb = Onb::from_w(normal);
+            (onb.u, onb.v)
+        } else {
+            (rec.tangent, rec.bitangent)
+        };
+
+        let perturbed = match self {
+            Bump::Normal { map, strength } => {
+                // A shading normal can line up with the tangent, leaving nothing of it
+                // in the surface plane.
+                let t = tangent - normal.dot(tangent) * normal;
+                let t = if t.near_zero() {
+                    Onb::from_w(normal).u
+                } else {
+                    t.unit_vector()
+                };
+                let b = bitangent - normal.dot(bitangent) * normal - t.dot(bitangent) * t;
+                let b = if b.near_zero() {
+                    normal.cross(t)
+                } else {
+                    b.unit_vector()
+                };
+
+                let c = map.value(rec) * 2.0 - Color::new(1.0, 1.0, 1.0);
+                *strength * (c.x * t + c.y * b) + c.z * normal
+            }
+            Bump::Height { map, scale } => {
+                let height = |du: f64, dv: f64| {
+                    let mut shifted = *rec;
+                    let offset = du * tangent + dv * bitangent;
+                    shifted.u += du;
+                    shifted.v += dv;
+                    shifted.p += offset;
+                    shifted.local += offset;
+                    let c = map.value(&shifted);
+                    scale * (c.x + c.y + c.z) / 3.0
+                };
+                let dh_du = (height(DELTA, 0.0) - height(-DELTA, 0.0)) / (2.0 * DELTA);
+                let dh_dv = (height(0.0, DELTA) - height(0.0, -DELTA)) / (2.0 * DELTA);
+
+                // Normal of the surface displaced by the height along the normal.
+                let n = (tangent + dh_du * normal).cross(bitangent + dh_dv * normal);
+                if n.dot(normal) < 0.0 {
+                    -n
+                } else {
+                    n
+                }
+            }
+        };
+
+        if !perturbed.near_zero() {
+            rec.normal = flip * perturbed.unit_vector();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::bump::Bump;
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::material::Material;
+    use crate::quad::Quad;
+    use crate::ray::Ray;
+    use crate::texture::{SolidColor, Texture};
+    use crate::vec3::{Color, Point3, Vec3};
+
+    /// Height growing along x, so slopes tilt normals towards -x.
+    #[derive(Debug)]
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, rec: &HitRecord) -> Color {
+            Color::new(rec.p.x, rec.p.x, rec.p.x)
+        }
+    }
+
+    fn floor() -> Quad {
+        // u runs along +x and v along -z, so u × v faces up.
+        Quad::new(
+            Point3::new(-1.0, 0.0, 1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -2.0),
+            Material::None,
+        )
+    }
+
+    fn hit(quad: &Quad, from_below: bool) -> HitRecord<'_> {
+        let ray = if from_below {
+            Ray::new(Point3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0))
+        } else {
+            Ray::new(Point3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0))
+        };
+        quad.hit(&ray, 0.001, f64::INFINITY).unwrap()
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_normal() {
+        let quad = floor();
+        let mut record = hit(&quad, false);
+        let flat = Bump::Normal {
+            map: Arc::new(SolidColor::new(Color::new(0.5, 0.5, 1.0))),
+            strength: 1.0,
+        };
+        flat.apply(&mut record);
+        assert!((record.normal - Vec3::new(0.0, 1.0, 0.0)).near_zero());
+    }
+
+    #[test]
+    fn normal_map_tilts_in_tangent_space() {
+        let quad = floor();
+        let bump = Bump::Normal {
+            map: Arc::new(SolidColor::new(Color::new(1.0, 0.5, 1.0))),
+            strength: 1.0,
+        };
+        let expected = Vec3::new(1.0, 1.0, 0.0).unit_vector();
+
+        let mut record = hit(&quad, false);
+        bump.apply(&mut record);
+        assert!((record.normal - expected).near_zero());
+        assert_eq!(record.geometric_normal, Vec3::new(0.0, 1.0, 0.0));
+
+        // From below the same tilt is seen mirrored through the surface.
+        let mut record = hit(&quad, true);
+        bump.apply(&mut record);
+        assert!((record.normal + expected).near_zero());
+        assert_eq!(record.geometric_normal, Vec3::new(0.0, -1.0, 0.0));
+
+        let weaker = Bump::Normal {
+            map: Arc::new(SolidColor::new(Color::new(1.0, 0.5, 1.0))),
+            strength: 0.5,
+        };
+        let mut record = hit(&quad, false);
+        weaker.apply(&mut record);
+        assert!(record.normal.y > expected.y);
+    }
+
+    #[test]
+    fn normal_map_with_tangent_along_normal() {
+        let quad = floor();
+        let mut record = hit(&quad, false);
+        record.tangent = Vec3::new(0.0, 3.0, 0.0);
+        let flat = Bump::Normal {
+            map: Arc::new(SolidColor::new(Color::new(0.5, 0.5, 1.0))),
+            strength: 1.0,
+        };
+        flat.apply(&mut record);
+        assert!((record.normal - Vec3::new(0.0, 1.0, 0.0)).near_zero());
+    }
+
+    #[test]
+    fn height_map_slopes() {
+        let quad = floor();
+        let mut record = hit(&quad, false);
+        let ramp = Bump::Height {
+            map: Arc::new(Ramp),
+            scale: 1.0,
+        };
+        ramp.apply(&mut record);
+
+        // A slope of one along x tilts the normal 45 degrees away from the rise.
+        let expected = Vec3::new(-1.0, 1.0, 0.0).unit_vector();
+        assert!((record.normal - expected).length() < 1e-6);
+
+        let mut record = hit(&quad, false);
+        let constant = Bump::Height {
+            map: Arc::new(SolidColor::new(Color::new(0.3, 0.3, 0.3))),
+            scale: 1.0,
+        };
+        constant.apply(&mut record);
+        assert!((record.normal - Vec3::new(0.0, 1.0, 0.0)).near_zero());
+    }
+}
@@ -0,0 +1,405 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::ray::Ray;
+use crate::vec3::Point3;
+
+const BIN_COUNT: usize = 16;
+const MAX_LEAF_SIZE: usize = 4;
+const TRAVERSAL_COST: f64 = 1.0;
+const INTERSECTION_COST: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BvhStats {
+    pub depth: usize,
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub min_leaf_size: usize,
+    pub max_leaf_size: usize,
+    pub primitive_count: usize,
+}
+
+impl BvhStats {
+    pub fn average_leaf_size(&self) -> f64 {
+        if self.leaf_count == 0 {
+            0.0
+        } else {
+            self.primitive_count as f64 / self.leaf_count as f64
+        }
+    }
+
+    fn record_leaf(&mut self, size: usize) {
+        self.min_leaf_size = if self.leaf_count == 0 {
+            size
+        } else {
+            self.min_leaf_size.min(size)
+        };
+        self.max_leaf_size = self.max_leaf_size.max(size);
+        self.leaf_count += 1;
+        self.primitive_count += size;
+    }
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} leaves, depth {}, leaf size {}..{} (avg {:.2})",
+            self.node_count,
+            self.leaf_count,
+            self.depth,
+            self.min_leaf_size,
+            self.max_leaf_size,
+            self.average_leaf_size()
+        )
+    }
+}
+
+enum BvhNode {
+    Leaf {
+        bounding_box: Aabb,
+        objects: Vec<Arc<dyn Hittable>>,
+    },
+    Interior {
+        bounding_box: Aabb,
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+    },
+}
+
+impl BvhNode {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        match self {
+            BvhNode::Leaf {
+                bounding_box,
+                objects,
+            } => {
+                if !bounding_box.hit(r, t_min, t_max) {
+                    return None;
+                }
+
+                let mut closest_so_far = t_max;
+                let mut hit_record = None;
+                for object in objects {
+                    if let Some(record) = object.hit(r, t_min, closest_so_far) {
+                        closest_so_far = record.t;
+                        hit_record = Some(record);
+                    }
+                }
+
+                hit_record
+            }
+            BvhNode::Interior {
+                bounding_box,
+                left,
+                right,
+            } => {
+                if !bounding_box.hit(r, t_min, t_max) {
+                    return None;
+                }
+
+                let left_hit = left.hit(r, t_min, t_max);
+                let closest_so_far = left_hit.as_ref().map_or(t_max, |record| record.t);
+                let right_hit = right.hit(r, t_min, closest_so_far);
+
+                right_hit.or(left_hit)
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self {
+            BvhNode::Leaf { bounding_box, .. } => *bounding_box,
+            BvhNode::Interior { bounding_box, .. } => *bounding_box,
+        }
+    }
+}
+
+struct BuildItem {
+    object: Arc<dyn Hittable>,
+    bounding_box: Aabb,
+    centroid: Point3,
+}
+
+#[derive(Copy, Clone)]
+struct Split {
+    axis: usize,
+    bin: usize,
+    cost: f64,
+}
+
+/// Bounding volume hierarchy over a set of objects, built top-down with a binned
+/// surface area heuristic. Objects without a bounding box are tested linearly.
+pub struct Bvh {
+    root: Option<BvhNode>,
+    unbounded: Vec<Arc<dyn Hittable>>,
+    stats: BvhStats,
+}
+
+impl Bvh {
+    pub fn new(objects: Vec<Arc<dyn Hittable>>) -> Bvh {
+        let mut items = vec![];
+        let mut unbounded = vec![];
+        for object in objects {
+            match object.bounding_box() {
+                Some(bounding_box) => items.push(BuildItem {
+                    object,
+                    bounding_box,
+                    centroid: bounding_box.centroid(),
+                }),
+                None => unbounded.push(object),
+            }
+        }
+
+        let mut stats = BvhStats::default();
+        let root = if items.is_empty() {
+            None
+        } else {
+            Some(build(items, 1, &mut stats))
+        };
+
+        Bvh {
+            root,
+            unbounded,
+            stats,
+        }
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        self.stats
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let mut hit_record = self
+            .root
+            .as_ref()
+            .and_then(|root| root.hit(r, t_min, t_max));
+        let mut closest_so_far = hit_record.as_ref().map_or(t_max, |record| record.t);
+
+        for object in self.unbounded.iter() {
+            if let Some(record) = object.hit(r, t_min, closest_so_far) {
+                closest_so_far = record.t;
+                hit_record = Some(record);
+            }
+        }
+
+        hit_record
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+
+        self.root.as_ref().map(|root| root.bounding_box())
+    }
+}
+
+fn build(items: Vec<BuildItem>, depth: usize, stats: &mut BvhStats) -> BvhNode {
+    stats.node_count += 1;
+    stats.depth = stats.depth.max(depth);
+
+    let bounding_box = items
+        .iter()
+        .map(|item| item.bounding_box)
+        .reduce(Aabb::surrounding_box)
+        .unwrap();
+    let centroid_box = items
+        .iter()
+        .map(|item| Aabb::new(item.centroid, item.centroid))
+        .reduce(Aabb::surrounding_box)
+        .unwrap();
+
+    let leaf_cost = INTERSECTION_COST * items.len() as f64;
+    let split = match find_split(&items, &bounding_box, &centroid_box) {
+        Some(split) if split.cost < leaf_cost || items.len() > MAX_LEAF_SIZE => split,
+        _ => {
+            stats.record_leaf(items.len());
+            return BvhNode::Leaf {
+                bounding_box,
+                objects: items.into_iter().map(|item| item.object).collect(),
+            };
+        }
+    };
+
+    let (left, right): (Vec<BuildItem>, Vec<BuildItem>) = items
+        .into_iter()
+        .partition(|item| bin_index(item.centroid, &centroid_box, split.axis) < split.bin);
+
+    BvhNode::Interior {
+        bounding_box,
+        left: Box::new(build(left, depth + 1, stats)),
+        right: Box::new(build(right, depth + 1, stats)),
+    }
+}
+
+fn bin_index(centroid: Point3, centroid_box: &Aabb, axis: usize) -> usize {
+    let extent = centroid_box.maximum[axis] - centroid_box.minimum[axis];
+    let offset = (centroid[axis] - centroid_box.minimum[axis]) / extent;
+    ((offset * BIN_COUNT as f64) as usize).min(BIN_COUNT - 1)
+}
+
+fn find_split(items: &[BuildItem], bounding_box: &Aabb, centroid_box: &Aabb) -> Option<Split> {
+    let parent_area = bounding_box.surface_area();
+    let mut best: Option<Split> = None;
+
+    for axis in 0..3 {
+        if centroid_box.maximum[axis] - centroid_box.minimum[axis] <= 0.0 {
+            continue;
+        }
+
+        let mut counts = [0usize; BIN_COUNT];
+        let mut boxes: [Option<Aabb>; BIN_COUNT] = [None; BIN_COUNT];
+        for item in items {
+            let bin = bin_index(item.centroid, centroid_box, axis);
+            counts[bin] += 1;
+            boxes[bin] = Some(match boxes[bin] {
+                Some(existing) => Aabb::surrounding_box(existing, item.bounding_box),
+                None => item.bounding_box,
+            });
+        }
+
+        // Sweep from the right so each split plane knows the area and count of
+        // everything above it, then sweep from the left to evaluate the cost.
+        let mut right_areas = [0.0; BIN_COUNT];
+        let mut right_counts = [0usize; BIN_COUNT];
+        let mut accumulated: Option<Aabb> = None;
+        let mut count = 0;
+        for bin in (1..BIN_COUNT).rev() {
+            accumulated = union(accumulated, boxes[bin]);
+            count += counts[bin];
+            right_areas[bin] = accumulated.map_or(0.0, |b| b.surface_area());
+            right_counts[bin] = count;
+        }
+
+        let mut accumulated: Option<Aabb> = None;
+        let mut count = 0;
+        for bin in 1..BIN_COUNT {
+            accumulated = union(accumulated, boxes[bin - 1]);
+            count += counts[bin - 1];
+            if count == 0 || right_counts[bin] == 0 {
+                continue;
+            }
+
+            let left_area = accumulated.map_or(0.0, |b| b.surface_area());
+            let cost = TRAVERSAL_COST
+                + INTERSECTION_COST
+                    * (left_area * count as f64 + right_areas[bin] * right_counts[bin] as f64)
+                    / parent_area;
+
+            if best.is_none_or(|b| cost < b.cost) {
+                best = Some(Split { axis, bin, cost });
+            }
+        }
+    }
+
+    best
+}
+
+fn union(a: Option<Aabb>, b: Option<Aabb>) -> Option<Aabb> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(Aabb::surrounding_box(a, b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::bvh::Bvh;
+    use crate::hittable::{Hittable, HittableList};
+    use crate::material::Material;
+    use crate::ray::Ray;
+    use crate::sphere::Sphere;
+    use crate::vec3::Vec3;
+
+    fn random_vec(rng: &mut StdRng, min: f64, max: f64) -> Vec3 {
+        Vec3::new(
+            rng.gen_range(min..max),
+            rng.gen_range(min..max),
+            rng.gen_range(min..max),
+        )
+    }
+
+    fn random_world(rng: &mut StdRng, count: usize) -> HittableList {
+        let mut list = HittableList::new();
+        for _ in 0..count {
+            let center = random_vec(rng, -20.0, 20.0);
+            let radius = rng.gen_range(0.05..1.5);
+            list.add(Sphere::new(center, radius, Material::None));
+        }
+        list
+    }
+
+    #[test]
+    fn empty_bvh() {
+        let bvh = Bvh::new(vec![]);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+
+        assert!(bvh.hit(&ray, 0.001, f64::INFINITY).is_none());
+        assert!(bvh.bounding_box().is_none());
+        assert_eq!(bvh.stats().node_count, 0);
+    }
+
+    #[test]
+    fn bvh_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let list = random_world(&mut rng, 1000);
+        let bvh = Bvh::new(list.objects.clone());
+
+        for _ in 0..5000 {
+            let origin = random_vec(&mut rng, -30.0, 30.0);
+            let direction = random_vec(&mut rng, -1.0, 1.0);
+            let ray = Ray::new(origin, direction);
+
+            let expected = list.hit(&ray, 0.001, f64::INFINITY);
+            let result = bvh.hit(&ray, 0.001, f64::INFINITY);
+
+            match (expected, result) {
+                (Some(expected), Some(result)) => {
+                    assert_eq!(expected.t, result.t);
+                    assert_eq!(expected.p, result.p);
+                    assert_eq!(expected.normal, result.normal);
+                }
+                (None, None) => {}
+                _ => panic!("BVH and brute force disagree for {:?}", ray),
+            }
+        }
+    }
+
+    #[test]
+    fn bvh_stats() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let list = random_world(&mut rng, 500);
+        let bvh = Bvh::new(list.objects);
+        let stats = bvh.stats();
+
+        assert_eq!(stats.primitive_count, 500);
+        assert_eq!(stats.node_count, 2 * stats.leaf_count - 1);
+        assert!(stats.min_leaf_size >= 1);
+        assert!(stats.max_leaf_size >= stats.min_leaf_size);
+        assert!(stats.depth > 1 && stats.depth < 500);
+    }
+
+    #[test]
+    fn bvh_bounding_box() {
+        let objects: Vec<Arc<dyn Hittable>> = vec![
+            Arc::new(Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, Material::None)),
+            Arc::new(Sphere::new(Vec3::new(5.0, 0.0, 0.0), 2.0, Material::None)),
+        ];
+        let bvh = Bvh::new(objects);
+        let bounding_box = bvh.bounding_box().unwrap();
+
+        assert_eq!(bounding_box.minimum, Vec3::new(-1.0, -2.0, -2.0));
+        assert_eq!(bounding_box.maximum, Vec3::new(7.0, 2.0, 2.0));
+    }
+}
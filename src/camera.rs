@@ -1,4 +1,6 @@
 use crate::{
+    hittable::Hittable,
+    random::random_range,
     ray::Ray,
     vec3::{Point3, Vec3},
 };
@@ -8,6 +10,13 @@ pub struct Camera {
     pub lower_left_corner: Point3,
     pub horizontal: Vec3,
     pub vertical: Vec3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+    pub lens_radius: f64,
+    pub focus_dist: f64,
+    pub time0: f64,
+    pub time1: f64,
 }
 
 impl Camera {
@@ -25,13 +34,112 @@ impl Camera {
             lower_left_corner,
             horizontal,
             vertical,
+            u: Vec3::new(1.0, 0.0, 0.0),
+            v: Vec3::new(0.0, 1.0, 0.0),
+            w: Vec3::new(0.0, 0.0, 1.0),
+            lens_radius: 0.0,
+            focus_dist: focal_length,
+            time0: 0.0,
+            time1: 0.0,
         }
     }
 
-    pub fn get_ray(&self, u: f64, v: f64) -> Ray {
-        Ray::new(
+    /// Camera at `look_from` pointing towards `look_at`, with `vup` giving the
+    /// rough up direction and `vfov` the vertical field of view in degrees.
+    ///
+    /// Panics if `look_from` equals `look_at` or `vup` is parallel to the view direction,
+    /// since neither defines an orientation.
+    pub fn look_at(
+        look_from: Point3,
+        look_at: Point3,
+        vup: Vec3,
+        vfov: f64,
+        aspect_ratio: f64,
+    ) -> Camera {
+        let h = (vfov.to_radians() / 2.0).tan();
+        let viewport_height = 2.0 * h;
+        let viewport_width = aspect_ratio * viewport_height;
+
+        let w = (look_from - look_at).unit_vector();
+        let u = vup.cross(w).unit_vector();
+        let v = w.cross(u);
+
+        let origin = look_from;
+        let horizontal = viewport_width * u;
+        let vertical = viewport_height * v;
+        let lower_left_corner = origin - horizontal / 2.0 - vertical / 2.0 - w;
+
+        Camera {
+            origin,
+            lower_left_corner,
+            horizontal,
+            vertical,
+            u,
+            v,
+            w,
+            lens_radius: 0.0,
+            focus_dist: 1.0,
+            time0: 0.0,
+            time1: 0.0,
+        }
+    }
+
+    /// Turns the camera into a thin lens with the given aperture diameter, keeping the
+    /// field of view and moving the plane of perfect focus to `focus_dist` along the
+    /// view direction. An aperture of zero gives a pinhole camera.
+    pub fn set_focus(&mut self, aperture: f64, focus_dist: f64) {
+        let scale = focus_dist / self.focus_dist;
+        self.horizontal *= scale;
+        self.vertical *= scale;
+        self.lower_left_corner =
+            self.origin - self.horizontal / 2.0 - self.vertical / 2.0 - focus_dist * self.w;
+        self.lens_radius = aperture / 2.0;
+        self.focus_dist = focus_dist;
+    }
+
+    /// Opens the shutter between `time0` and `time1`; each ray gets a uniformly
+    /// random time in that interval.
+    pub fn set_shutter(&mut self, time0: f64, time1: f64) {
+        self.time0 = time0;
+        self.time1 = time1;
+    }
+
+    /// Shoots a probe ray through the viewport position `(u, v)` and focuses on
+    /// whatever it hits first, keeping the current aperture. Returns the new focus
+    /// distance, or `None` (leaving the camera unchanged) when the probe misses.
+    pub fn autofocus(&mut self, world: &dyn Hittable, u: f64, v: f64) -> Option<f64> {
+        let probe = Ray::new(
             self.origin,
             self.lower_left_corner + self.horizontal * u + self.vertical * v - self.origin,
+        );
+        let record = world.hit(&probe, 0.001, f64::INFINITY)?;
+        let focus_dist = (record.p - self.origin).dot(-self.w);
+        if focus_dist <= 0.0 {
+            return None;
+        }
+
+        self.set_focus(2.0 * self.lens_radius, focus_dist);
+        Some(focus_dist)
+    }
+
+    pub fn get_ray(&self, u: f64, v: f64) -> Ray {
+        let offset = if self.lens_radius > 0.0 {
+            let rd = self.lens_radius * Vec3::random_in_unit_disk();
+            self.u * rd.x + self.v * rd.y
+        } else {
+            Vec3::new(0.0, 0.0, 0.0)
+        };
+
+        let time = if self.time1 > self.time0 {
+            random_range(self.time0, self.time1)
+        } else {
+            self.time0
+        };
+
+        Ray::with_time(
+            self.origin + offset,
+            self.lower_left_corner + self.horizontal * u + self.vertical * v - self.origin - offset,
+            time,
         )
     }
 }
@@ -39,7 +147,10 @@ impl Camera {
 #[cfg(test)]
 mod tests {
     use crate::camera::Camera;
+    use crate::hittable::HittableList;
+    use crate::material::Material;
     use crate::ray::Ray;
+    use crate::sphere::Sphere;
     use crate::vec3::Vec3;
 
     #[test]
@@ -52,6 +163,41 @@ mod tests {
         assert_eq!(camera.lower_left_corner, Vec3::new(-3.0, -1.5, -1.0));
     }
 
+    #[test]
+    fn look_at_camera() {
+        let camera = Camera::look_at(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            2.0,
+        );
+        let reference = Camera::new(2.0, 2.0, 1.0);
+
+        assert_eq!(camera.origin, reference.origin);
+        assert!((camera.horizontal - reference.horizontal).near_zero());
+        assert!((camera.vertical - reference.vertical).near_zero());
+        assert!((camera.lower_left_corner - reference.lower_left_corner).near_zero());
+    }
+
+    #[test]
+    fn look_at_camera_basis() {
+        let camera = Camera::look_at(
+            Vec3::new(3.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            60.0,
+            1.5,
+        );
+
+        assert_eq!(camera.w, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(camera.u, Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(camera.v, Vec3::new(0.0, 1.0, 0.0));
+
+        let centre = camera.get_ray(0.5, 0.5);
+        assert!((centre.direction.unit_vector() - Vec3::new(-1.0, 0.0, 0.0)).near_zero());
+    }
+
     #[test]
     fn get_ray() {
         let camera = Camera::new(2.0, 3.0, 1.0);
@@ -62,4 +208,50 @@ mod tests {
             Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(57.0, 28.5, -1.0))
         )
     }
+
+    #[test]
+    fn set_focus() {
+        let mut camera = Camera::new(2.0, 2.0, 1.0);
+        camera.set_focus(0.5, 4.0);
+
+        assert_eq!(camera.lens_radius, 0.25);
+        assert_eq!(camera.horizontal, Vec3::new(16.0, 0.0, 0.0));
+        assert_eq!(camera.vertical, Vec3::new(0.0, 8.0, 0.0));
+        assert_eq!(camera.lower_left_corner, Vec3::new(-8.0, -4.0, -4.0));
+
+        // Every lens sample must still pass through the same point on the focus plane.
+        for _ in 0..100 {
+            let ray = camera.get_ray(0.25, 0.75);
+            let t = (-4.0 - ray.origin.z) / ray.direction.z;
+            assert!((ray.at(t) - Vec3::new(-4.0, 2.0, -4.0)).near_zero());
+            assert!((ray.origin - camera.origin).length_squared() <= 0.25 * 0.25);
+        }
+    }
+
+    #[test]
+    fn autofocus() {
+        let mut world = HittableList::new();
+        world.add(Sphere::new(Vec3::new(0.0, 0.0, -6.0), 1.0, Material::None));
+        let mut camera = Camera::new(2.0, 2.0, 1.0);
+        camera.set_focus(0.1, 1.0);
+
+        assert_eq!(camera.autofocus(&world, 0.5, 0.5), Some(5.0));
+        assert_eq!(camera.focus_dist, 5.0);
+        assert_eq!(camera.lens_radius, 0.05);
+
+        assert_eq!(camera.autofocus(&world, 0.0, 0.0), None);
+        assert_eq!(camera.focus_dist, 5.0);
+    }
+
+    #[test]
+    fn shutter() {
+        let mut camera = Camera::new(2.0, 2.0, 1.0);
+        assert_eq!(camera.get_ray(0.5, 0.5).time, 0.0);
+
+        camera.set_shutter(1.0, 2.0);
+        for _ in 0..100 {
+            let time = camera.get_ray(0.5, 0.5).time;
+            assert!((1.0..2.0).contains(&time));
+        }
+    }
 }
@@ -0,0 +1,300 @@
+use std::path::PathBuf;
+
+use raytracing::presets::PRESETS;
+use raytracing::render::OutputFormat;
+use raytracing::tonemap::{Operator, ToneMapping};
+
+pub const USAGE: &str = "\
+Usage: raytracing [OPTIONS]
+
+Renders a scene file or a built-in preset and writes the image to disk.
+
+Options:
+  -s, --scene <FILE>       JSON scene file to render
+  -p, --preset <NAME>      Built-in scene to render instead of a scene file
+                           (three-spheres, random-spheres, motion-blur,
+                           cornell-box)
+  -o, --output <FILE>      Output image path [default: output.ppm]
+  -f, --format <FORMAT>    Output format, otherwise taken from the output extension
+                           (ppm, p6, png, jpg, tga, bmp, tiff, exr, hdr)
+  -W, --width <PIXELS>     Image width; keeps the scene aspect ratio unless
+                           --height is also given
+  -H, --height <PIXELS>    Image height; keeps the scene aspect ratio unless
+                           --width is also given
+  -n, --samples <COUNT>    Samples per pixel
+  -d, --max-depth <COUNT>  Maximum number of bounces per path
+  -e, --exposure <STOPS>   Exposure adjustment before tone mapping [default: 0]
+  -t, --tonemap <CURVE>    Tone mapping curve for 8-bit formats (clamp, reinhard,
+                           reinhard-extended, aces, hable) [default: clamp]
+      --white-point <VALUE>
+                           Radiance mapped to white by reinhard-extended [default: 4]
+      --seed <NUMBER>      Random seed, the same seed gives the same image
+  -j, --threads <COUNT>    Worker threads, 0 uses every core [default: 0]
+  -h, --help               Print this help
+";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Scene(PathBuf),
+    Preset(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub source: Source,
+    pub output: PathBuf,
+    pub format: OutputFormat,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub samples_per_pixel: Option<usize>,
+    pub max_depth: Option<isize>,
+    pub seed: Option<u64>,
+    pub threads: usize,
+    pub tone_mapping: ToneMapping,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Render(Options),
+    Help,
+}
+
+/// Parses the arguments following the program name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut scene = None;
+    let mut preset = None;
+    let mut output = None;
+    let mut format = None;
+    let mut width = None;
+    let mut height = None;
+    let mut samples_per_pixel = None;
+    let mut max_depth = None;
+    let mut seed = None;
+    let mut threads = 0;
+    let mut exposure = 0.0;
+    let mut operator = String::from("clamp");
+    let mut white_point = 4.0;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // Accept both "--option value" and "--option=value".
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let inline_value = inline_value.map(str::to_string);
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("'{}' needs a value", name))
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-s" | "--scene" => scene = Some(PathBuf::from(value()?)),
+            "-p" | "--preset" => {
+                let name = value()?;
+                if !PRESETS.contains(&name.as_str()) {
+                    return Err(format!(
+                        "unknown preset '{}', expected one of: {}",
+                        name,
+                        PRESETS.join(", ")
+                    ));
+                }
+                preset = Some(name);
+            }
+            "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+            "-f" | "--format" => {
+                let name = value()?;
+                format = Some(
+                    OutputFormat::from_name(&name)
+                        .ok_or_else(|| format!("unknown output format '{}'", name))?,
+                );
+            }
+            "-W" | "--width" => width = Some(positive(&name, &value()?)?),
+            "-H" | "--height" => height = Some(positive(&name, &value()?)?),
+            "-n" | "--samples" => samples_per_pixel = Some(positive(&name, &value()?)? as usize),
+            "-d" | "--max-depth" => max_depth = Some(positive(&name, &value()?)? as isize),
+            "-e" | "--exposure" => exposure = float(&name, &value()?)?,
+            "-t" | "--tonemap" => operator = value()?,
+            "--white-point" => {
+                white_point = float(&name, &value()?)?;
+                if white_point <= 0.0 {
+                    return Err(format!("'{}' must be positive", name));
+                }
+            }
+            "--seed" => seed = Some(number(&name, &value()?)?),
+            "-j" | "--threads" => threads = number(&name, &value()?)? as usize,
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    let source = match (scene, preset) {
+        (Some(_), Some(_)) => return Err(String::from("use either --scene or --preset")),
+        (Some(scene), None) => Source::Scene(scene),
+        (None, Some(preset)) => Source::Preset(preset),
+        (None, None) => Source::Preset(String::from("three-spheres")),
+    };
+
+    let output = output.unwrap_or_else(|| PathBuf::from("output.ppm"));
+    let format = match format {
+        Some(format) => format,
+        None => OutputFormat::from_path(&output).ok_or_else(|| {
+            format!(
+                "cannot tell the format of '{}', pass --format",
+                output.display()
+            )
+        })?,
+    };
+
+    let operator = Operator::from_name(&operator, white_point)
+        .ok_or_else(|| format!("unknown tone mapping curve '{}'", operator))?;
+
+    Ok(Command::Render(Options {
+        source,
+        output,
+        format,
+        width,
+        height,
+        samples_per_pixel,
+        max_depth,
+        seed,
+        threads,
+        tone_mapping: ToneMapping::new(exposure, operator),
+    }))
+}
+
+fn number(name: &str, value: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{}' expects a non-negative integer, got '{}'", name, value))
+}
+
+fn float(name: &str, value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(format!("'{}' expects a number, got '{}'", name, value)),
+    }
+}
+
+fn positive(name: &str, value: &str) -> Result<u32, String> {
+    match value.parse() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!(
+            "'{}' expects a positive integer, got '{}'",
+            name, value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use raytracing::render::OutputFormat;
+    use raytracing::tonemap::{Operator, ToneMapping};
+
+    use crate::cli::{parse_args, Command, Options, Source};
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn options(args: &[&str]) -> Options {
+        match parse(args) {
+            Ok(Command::Render(options)) => options,
+            other => panic!("expected render options, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults() {
+        let options = options(&[]);
+
+        assert_eq!(
+            options.source,
+            Source::Preset(String::from("three-spheres"))
+        );
+        assert_eq!(options.output, PathBuf::from("output.ppm"));
+        assert_eq!(options.format, OutputFormat::Ppm);
+        assert_eq!(options.width, None);
+        assert_eq!(options.threads, 0);
+        assert_eq!(options.tone_mapping, ToneMapping::default());
+    }
+
+    #[test]
+    fn all_options() {
+        let options = options(&[
+            "--scene",
+            "scenes/three_spheres.json",
+            "-o",
+            "render.out",
+            "--format=png",
+            "-W",
+            "800",
+            "--height=450",
+            "-n",
+            "16",
+            "--max-depth",
+            "8",
+            "--seed",
+            "42",
+            "-j",
+            "4",
+            "--exposure",
+            "-1.5",
+            "--tonemap",
+            "reinhard-extended",
+            "--white-point",
+            "2",
+        ]);
+
+        assert_eq!(
+            options.source,
+            Source::Scene(PathBuf::from("scenes/three_spheres.json"))
+        );
+        assert_eq!(options.output, PathBuf::from("render.out"));
+        assert_eq!(options.format, OutputFormat::Png);
+        assert_eq!(options.width, Some(800));
+        assert_eq!(options.height, Some(450));
+        assert_eq!(options.samples_per_pixel, Some(16));
+        assert_eq!(options.max_depth, Some(8));
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.threads, 4);
+        assert_eq!(
+            options.tone_mapping,
+            ToneMapping::new(-1.5, Operator::ReinhardExtended { white_point: 2.0 })
+        );
+    }
+
+    #[test]
+    fn format_from_output() {
+        assert_eq!(options(&["-o", "render.jpg"]).format, OutputFormat::Jpeg);
+        assert_eq!(
+            options(&["-o", "render.ppm", "-f", "p6"]).format,
+            OutputFormat::PpmBinary
+        );
+    }
+
+    #[test]
+    fn help() {
+        assert_eq!(parse(&["--width", "10", "--help"]), Ok(Command::Help));
+        assert_eq!(parse(&["-h"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse(&["--bogus"]).is_err());
+        assert!(parse(&["--width"]).is_err());
+        assert!(parse(&["--width", "0"]).is_err());
+        assert!(parse(&["--samples", "lots"]).is_err());
+        assert!(parse(&["--preset", "teapot"]).is_err());
+        assert!(parse(&["--preset", "motion-blur", "--scene", "a.json"]).is_err());
+        assert!(parse(&["--output", "render.out"]).is_err());
+        assert!(parse(&["--format", "gif"]).is_err());
+        assert!(parse(&["--tonemap", "filmic"]).is_err());
+        assert!(parse(&["--exposure", "bright"]).is_err());
+        assert!(parse(&["--white-point", "0"]).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+/// Piecewise-constant distribution over `0..1` with one step per value of `func`.
+#[derive(Debug, Clone)]
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    /// Builds the distribution from non-negative step values. If every value is zero
+    /// the distribution falls back to uniform.
+    pub fn new(func: &[f64]) -> Distribution1D {
+        assert!(!func.is_empty(), "distribution needs at least one value");
+        let n = func.len() as f64;
+
+        let mut cdf = Vec::with_capacity(func.len() + 1);
+        cdf.push(0.0);
+        for (i, value) in func.iter().enumerate() {
+            cdf.push(cdf[i] + value.max(0.0) / n);
+        }
+
+        let integral = cdf[func.len()];
+        for (i, c) in cdf.iter_mut().enumerate() {
+            *c = if integral > 0.0 {
+                *c / integral
+            } else {
+                i as f64 / n
+            };
+        }
+
+        Distribution1D {
+            func: func.iter().map(|value| value.max(0.0)).collect(),
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// Integral of the step function over `0..1`.
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Maps a uniform `u` in `0..1` to a point in `0..1`, returning the point, its
+    /// density and the index of the step it fell in.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        let offset = (self.cdf.partition_point(|&c| c <= u).max(1) - 1).min(self.count() - 1);
+
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 {
+            (u - self.cdf[offset]) / width
+        } else {
+            0.0
+        };
+
+        let x = (offset as f64 + du) / self.count() as f64;
+        (x.min(1.0 - f64::EPSILON), self.density(offset), offset)
+    }
+
+    /// Density at `x` in `0..1`.
+    pub fn pdf(&self, x: f64) -> f64 {
+        let index = ((x * self.count() as f64) as usize).min(self.count() - 1);
+        self.density(index)
+    }
+
+    fn density(&self, index: usize) -> f64 {
+        if self.integral > 0.0 {
+            self.func[index] / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Piecewise-constant distribution over the unit square, stored as rows of `width`
+/// values from `v = 0` to `v = 1`. Rows are picked from the marginal distribution
+/// and the column from that row's conditional one.
+#[derive(Debug, Clone)]
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(values: &[f64], width: usize, height: usize) -> Distribution2D {
+        assert_eq!(values.len(), width * height, "distribution size mismatch");
+
+        let conditional: Vec<Distribution1D> =
+            values.chunks(width).map(Distribution1D::new).collect();
+        let rows: Vec<f64> = conditional.iter().map(Distribution1D::integral).collect();
+
+        Distribution2D {
+            conditional,
+            marginal: Distribution1D::new(&rows),
+        }
+    }
+
+    /// Maps two uniform numbers to a point `(u, v)` and its density.
+    pub fn sample(&self, u1: f64, u2: f64) -> ((f64, f64), f64) {
+        let (v, row_pdf, row) = self.marginal.sample(u2);
+        let (u, column_pdf, _) = self.conditional[row].sample(u1);
+        ((u, v), row_pdf * column_pdf)
+    }
+
+    pub fn pdf(&self, u: f64, v: f64) -> f64 {
+        let rows = self.conditional.len();
+        let row = ((v * rows as f64) as usize).min(rows - 1);
+        self.marginal.pdf(v) * self.conditional[row].pdf(u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::distribution::{Distribution1D, Distribution2D};
+
+    #[test]
+    fn sample_1d() {
+        let distribution = Distribution1D::new(&[1.0, 0.0, 3.0]);
+        assert_eq!(distribution.integral(), 4.0 / 3.0);
+
+        let (x, pdf, index) = distribution.sample(0.1);
+        assert_eq!(index, 0);
+        assert!((x - 0.4 / 3.0).abs() < 1e-12);
+        assert_eq!(pdf, 0.75);
+
+        // Nothing ever lands in the empty step.
+        let (x, pdf, index) = distribution.sample(0.25);
+        assert_eq!(index, 2);
+        assert!((x - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(pdf, 2.25);
+        assert_eq!(distribution.pdf(0.5), 0.0);
+
+        let (x, _, index) = distribution.sample(0.999_999);
+        assert_eq!(index, 2);
+        assert!(x < 1.0);
+    }
+
+    #[test]
+    fn zero_function_is_uniform() {
+        let distribution = Distribution1D::new(&[0.0, 0.0]);
+        let (x, pdf, index) = distribution.sample(0.75);
+
+        assert_eq!((x, pdf, index), (0.75, 1.0, 1));
+    }
+
+    #[test]
+    fn sample_2d_matches_pdf() {
+        let values = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+        let distribution = Distribution2D::new(&values, 4, 2);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let mut counts = [0usize; 8];
+        let samples = 100_000;
+        for _ in 0..samples {
+            let ((u, v), pdf) = distribution.sample(rng.gen(), rng.gen());
+            assert!((pdf - distribution.pdf(u, v)).abs() < 1e-12);
+            counts[(v * 2.0) as usize * 4 + (u * 4.0) as usize] += 1;
+        }
+
+        // Each cell is picked in proportion to its value.
+        let total: f64 = values.iter().sum();
+        for (count, value) in counts.iter().zip(values) {
+            let expected = value / total;
+            assert!((*count as f64 / samples as f64 - expected).abs() < 0.01);
+        }
+
+        // The density integrates to one over the unit square.
+        let integral: f64 = (0..8)
+            .map(|i| distribution.pdf((i % 4) as f64 / 4.0 + 0.1, (i / 4) as f64 / 2.0 + 0.1) / 8.0)
+            .sum();
+        assert!((integral - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,1009 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fmt, fs, io};
+
+use serde_json::Value;
+
+use crate::camera::Camera;
+use crate::hittable::HittableList;
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::texture::VertexColor;
+use crate::triangle::{MeshData, TriangleMesh};
+use crate::vec3::{Color, Point3, Vec3};
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+const MODE_TRIANGLES: u64 = 4;
+
+#[derive(Debug)]
+pub enum GltfError {
+    Io { path: PathBuf, error: io::Error },
+    Json(serde_json::Error),
+    Glb(String),
+    Invalid { location: String, message: String },
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GltfError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            GltfError::Json(error) => write!(f, "invalid JSON: {}", error),
+            GltfError::Glb(message) => write!(f, "invalid GLB container: {}", message),
+            GltfError::Invalid { location, message } => write!(f, "{}: {}", location, message),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+/// Objects and the first camera found while walking a glTF scene. Node transforms are
+/// baked into the mesh vertices.
+pub struct GltfScene {
+    pub objects: HittableList,
+    pub camera: Option<Camera>,
+}
+
+/// Loads a `.gltf` or `.glb` file. Side-car buffers are resolved relative to the file;
+/// nothing is fetched over the network.
+pub fn load_gltf(path: impl AsRef<Path>, aspect_ratio: f64) -> Result<GltfScene, GltfError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|error| GltfError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+
+    parse_gltf(&bytes, path.parent(), aspect_ratio)
+}
+
+/// Parses glTF JSON or a GLB container. `base_directory` is used to resolve relative
+/// buffer URIs; without it only embedded and GLB buffers are available.
+///
+/// Metallic-roughness materials are mapped as follows: a non-black `emissiveFactor`
+/// (scaled by `KHR_materials_emissive_strength`) becomes a `DiffuseLight`, a non-zero
+/// `KHR_materials_transmission` factor becomes a `Dielectric` using
+/// `KHR_materials_ior` (default 1.5), `metallicFactor >= 0.5` becomes a `Metal` with the
+/// base color as albedo and the roughness as fuzz, anything else a `Lambertian`.
+/// `COLOR_0` vertex colors, times the base color, replace the albedo where present.
+pub fn parse_gltf(
+    bytes: &[u8],
+    base_directory: Option<&Path>,
+    aspect_ratio: f64,
+) -> Result<GltfScene, GltfError> {
+    let (json, binary_chunk) = if bytes.starts_with(GLB_MAGIC) {
+        split_glb(bytes)?
+    } else {
+        (bytes, None)
+    };
+    let document: Value = serde_json::from_slice(json).map_err(GltfError::Json)?;
+
+    let buffers = load_buffers(&document, binary_chunk, base_directory)?;
+    let importer = Importer {
+        document: &document,
+        buffers,
+        aspect_ratio,
+    };
+
+    let mut scene = GltfScene {
+        objects: HittableList::new(),
+        camera: None,
+    };
+    for node in importer.root_nodes()? {
+        importer.visit_node(node, IDENTITY, &mut vec![], &mut scene)?;
+    }
+
+    Ok(scene)
+}
+
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), GltfError> {
+    let read_u32 = |offset: usize| -> Result<u32, GltfError> {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| GltfError::Glb(String::from("unexpected end of file")))
+    };
+
+    let version = read_u32(4)?;
+    if version != 2 {
+        return Err(GltfError::Glb(format!("unsupported version {}", version)));
+    }
+    let length = (read_u32(8)? as usize).min(bytes.len());
+
+    let mut json = None;
+    let mut binary = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = read_u32(offset)? as usize;
+        let chunk_type = read_u32(offset + 4)?;
+        let data = bytes
+            .get(offset + 8..offset + 8 + chunk_length)
+            .ok_or_else(|| GltfError::Glb(String::from("chunk extends past end of file")))?;
+        match chunk_type {
+            GLB_JSON_CHUNK if json.is_none() => json = Some(data),
+            GLB_BIN_CHUNK if binary.is_none() => binary = Some(data),
+            _ => {}
+        }
+        offset += 8 + chunk_length;
+    }
+
+    let json = json.ok_or_else(|| GltfError::Glb(String::from("missing JSON chunk")))?;
+    Ok((json, binary))
+}
+
+fn load_buffers(
+    document: &Value,
+    binary_chunk: Option<&[u8]>,
+    base_directory: Option<&Path>,
+) -> Result<Vec<Vec<u8>>, GltfError> {
+    let mut buffers = vec![];
+    for (index, buffer) in array(document, "buffers").iter().enumerate() {
+        let location = format!("buffers[{}]", index);
+        let data = match buffer.get("uri").and_then(Value::as_str) {
+            Some(uri) if uri.starts_with("data:") => {
+                let encoded = uri
+                    .split_once(";base64,")
+                    .map(|(_, encoded)| encoded)
+                    .ok_or_else(|| invalid(&location, "only base64 data URIs are supported"))?;
+                decode_base64(encoded).ok_or_else(|| invalid(&location, "invalid base64 data"))?
+            }
+            Some(uri) => {
+                let directory = base_directory.ok_or_else(|| {
+                    invalid(&location, "external buffer without a base directory")
+                })?;
+                let path = directory.join(percent_decode(uri));
+                fs::read(&path).map_err(|error| GltfError::Io { path, error })?
+            }
+            None => binary_chunk
+                .filter(|_| index == 0)
+                .map(|chunk| chunk.to_vec())
+                .ok_or_else(|| invalid(&location, "buffer has no uri and no GLB chunk"))?,
+        };
+        buffers.push(data);
+    }
+
+    Ok(buffers)
+}
+
+struct Importer<'a> {
+    document: &'a Value,
+    buffers: Vec<Vec<u8>>,
+    aspect_ratio: f64,
+}
+
+impl Importer<'_> {
+    fn root_nodes(&self) -> Result<Vec<usize>, GltfError> {
+        let scenes = array(self.document, "scenes");
+        if !scenes.is_empty() {
+            let scene_index = self
+                .document
+                .get("scene")
+                .and_then(Value::as_u64)
+                .unwrap_or(0) as usize;
+            let scene = scenes
+                .get(scene_index)
+                .ok_or_else(|| invalid("scene", "scene index out of range"))?;
+            return indices(scene, "nodes", &format!("scenes[{}]", scene_index));
+        }
+
+        // Without scenes every node that is nobody's child is a root.
+        let nodes = array(self.document, "nodes");
+        let mut is_child = vec![false; nodes.len()];
+        for (index, node) in nodes.iter().enumerate() {
+            for child in indices(node, "children", &format!("nodes[{}]", index))? {
+                if let Some(flag) = is_child.get_mut(child) {
+                    *flag = true;
+                }
+            }
+        }
+
+        Ok((0..nodes.len()).filter(|&i| !is_child[i]).collect())
+    }
+
+    /// Walks `index` and its children. `ancestors` holds the nodes on the current path
+    /// so a cyclic hierarchy is reported instead of recursing forever.
+    fn visit_node(
+        &self,
+        index: usize,
+        parent: Matrix,
+        ancestors: &mut Vec<usize>,
+        scene: &mut GltfScene,
+    ) -> Result<(), GltfError> {
+        let location = format!("nodes[{}]", index);
+        if ancestors.contains(&index) {
+            return Err(invalid(&location, "node hierarchy contains a cycle"));
+        }
+        let node = array(self.document, "nodes")
+            .get(index)
+            .ok_or_else(|| invalid(&location, "node index out of range"))?;
+        let transform = multiply(&parent, &local_transform(node, &location)?);
+
+        if let Some(mesh) = node.get("mesh").and_then(Value::as_u64) {
+            self.add_mesh(mesh as usize, &transform, &mut scene.objects)?;
+        }
+
+        if scene.camera.is_none() {
+            if let Some(camera) = node.get("camera").and_then(Value::as_u64) {
+                scene.camera = Some(self.camera(camera as usize, &transform)?);
+            }
+        }
+
+        ancestors.push(index);
+        for child in indices(node, "children", &location)? {
+            self.visit_node(child, transform, ancestors, scene)?;
+        }
+        ancestors.pop();
+
+        Ok(())
+    }
+
+    fn add_mesh(
+        &self,
+        index: usize,
+        transform: &Matrix,
+        objects: &mut HittableList,
+    ) -> Result<(), GltfError> {
+        let location = format!("meshes[{}]", index);
+        let mesh = array(self.document, "meshes")
+            .get(index)
+            .ok_or_else(|| invalid(&location, "mesh index out of range"))?;
+        let normal_transform = normal_matrix(transform);
+
+        for (primitive_index, primitive) in array(mesh, "primitives").iter().enumerate() {
+            let location = format!("{}.primitives[{}]", location, primitive_index);
+            let mode = primitive
+                .get("mode")
+                .and_then(Value::as_u64)
+                .unwrap_or(MODE_TRIANGLES);
+            if mode != MODE_TRIANGLES {
+                return Err(invalid(&location, "only triangle primitives are supported"));
+            }
+
+            let attributes = primitive
+                .get("attributes")
+                .ok_or_else(|| invalid(&location, "missing attributes"))?;
+            let attribute = |name: &str| attributes.get(name).and_then(Value::as_u64);
+
+            let position_accessor = attribute("POSITION")
+                .ok_or_else(|| invalid(&location, "missing POSITION attribute"))?;
+            let (values, components) =
+                self.read_attribute(position_accessor as usize, &[3], "POSITION", &location)?;
+            let positions: Vec<Point3> = values
+                .chunks(components)
+                .map(|p| transform_point(transform, Point3::new(p[0], p[1], p[2])))
+                .collect();
+
+            let indices = match primitive.get("indices").and_then(Value::as_u64) {
+                Some(accessor) => {
+                    let (values, _) =
+                        self.read_attribute(accessor as usize, &[1], "indices", &location)?;
+                    values.iter().map(|&i| i as usize).collect::<Vec<_>>()
+                }
+                None => (0..positions.len()).collect(),
+            };
+            if indices.iter().any(|&i| i >= positions.len()) {
+                return Err(invalid(&location, "vertex index out of range"));
+            }
+            let triangles = indices
+                .chunks_exact(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect();
+
+            let material_index = primitive.get("material").and_then(Value::as_u64);
+            let material = match material_index {
+                Some(material) => self.material(material as usize)?,
+                None => Material::Lambertian(Lambertian::new(Color::new(0.8, 0.8, 0.8))),
+            };
+
+            let mut data = MeshData::new(positions, triangles, material);
+            if let Some(accessor) = attribute("NORMAL") {
+                let (values, components) =
+                    self.read_attribute(accessor as usize, &[3], "NORMAL", &location)?;
+                let normals = values
+                    .chunks(components)
+                    .map(|n| {
+                        let normal =
+                            transform_vector(&normal_transform, Vec3::new(n[0], n[1], n[2]));
+                        if normal.near_zero() {
+                            return Err(invalid(&location, "NORMAL has zero length"));
+                        }
+                        Ok(normal.unit_vector())
+                    })
+                    .collect::<Result<_, _>>()?;
+                data.normals = Some(normals);
+            }
+            if let Some(accessor) = attribute("TEXCOORD_0") {
+                let (values, components) =
+                    self.read_attribute(accessor as usize, &[2], "TEXCOORD_0", &location)?;
+                // glTF puts the UV origin at the top left, flip it to bottom left.
+                data.uvs = Some(
+                    values
+                        .chunks(components)
+                        .map(|uv| (uv[0], 1.0 - uv[1]))
+                        .collect(),
+                );
+            }
+            if let Some(accessor) = attribute("COLOR_0") {
+                let (values, components) =
+                    self.read_attribute(accessor as usize, &[3, 4], "COLOR_0", &location)?;
+                // COLOR_0 multiplies the base color, so bake the factor into the colors
+                // and have the albedo show them.
+                let tint = match material_index {
+                    Some(material) => {
+                        base_color(&array(self.document, "materials")[material as usize])
+                    }
+                    None => Color::new(1.0, 1.0, 1.0),
+                };
+                data.colors = Some(
+                    values
+                        .chunks(components)
+                        .map(|c| tint * Color::new(c[0], c[1], c[2]))
+                        .collect(),
+                );
+                data.material = match data.material {
+                    Material::Lambertian(mut l) => {
+                        l.albedo = Arc::new(VertexColor::new(l.albedo));
+                        Material::Lambertian(l)
+                    }
+                    Material::Metal(mut m) => {
+                        m.albedo = Arc::new(VertexColor::new(m.albedo));
+                        Material::Metal(m)
+                    }
+                    other => other,
+                };
+            }
+
+            let vertex_count = data.positions.len();
+            let counts_match = [
+                data.normals.as_ref().map(Vec::len),
+                data.uvs.as_ref().map(Vec::len),
+                data.colors.as_ref().map(Vec::len),
+            ]
+            .iter()
+            .flatten()
+            .all(|&count| count == vertex_count);
+            if !counts_match {
+                return Err(invalid(&location, "attribute counts do not match POSITION"));
+            }
+
+            objects.add(TriangleMesh::new(data));
+        }
+
+        Ok(())
+    }
+
+    fn material(&self, index: usize) -> Result<Material, GltfError> {
+        let location = format!("materials[{}]", index);
+        let material = array(self.document, "materials")
+            .get(index)
+            .ok_or_else(|| invalid(&location, "material index out of range"))?;
+
+        let pbr = material.get("pbrMetallicRoughness");
+        let factor = |name: &str| {
+            pbr.and_then(|pbr| pbr.get(name))
+                .and_then(Value::as_f64)
+                .unwrap_or(1.0)
+        };
+        let base_color = base_color(material);
+        let metallic = factor("metallicFactor");
+        let roughness = factor("roughnessFactor");
+
+        let extension = |name: &str, key: &str| {
+            material
+                .get("extensions")
+                .and_then(|extensions| extensions.get(name))
+                .and_then(|extension| extension.get(key))
+                .and_then(Value::as_f64)
+        };
+        let emissive = material
+            .get("emissiveFactor")
+            .and_then(Value::as_array)
+            .map(|c| {
+                let channel = |i: usize| c.get(i).and_then(Value::as_f64).unwrap_or(0.0);
+                Color::new(channel(0), channel(1), channel(2))
+            })
+            .unwrap_or_else(|| Color::new(0.0, 0.0, 0.0));
+        if emissive.length_squared() > 0.0 {
+            let strength = extension("KHR_materials_emissive_strength", "emissiveStrength");
+            return Ok(Material::DiffuseLight(DiffuseLight::new(
+                emissive * strength.unwrap_or(1.0),
+            )));
+        }
+
+        let transmission = extension("KHR_materials_transmission", "transmissionFactor");
+        if transmission.unwrap_or(0.0) > 0.0 {
+            let ior = extension("KHR_materials_ior", "ior").unwrap_or(1.5);
+            return Ok(Material::Dielectric(Dielectric::new(ior)));
+        }
+
+        if metallic >= 0.5 {
+            Ok(Material::Metal(Metal::new(base_color, roughness)))
+        } else {
+            Ok(Material::Lambertian(Lambertian::new(base_color)))
+        }
+    }
+
+    fn camera(&self, index: usize, transform: &Matrix) -> Result<Camera, GltfError> {
+        let location = format!("cameras[{}]", index);
+        let camera = array(self.document, "cameras")
+            .get(index)
+            .ok_or_else(|| invalid(&location, "camera index out of range"))?;
+        let yfov = camera
+            .get("perspective")
+            .and_then(|perspective| perspective.get("yfov"))
+            .and_then(Value::as_f64)
+            .ok_or_else(|| invalid(&location, "only perspective cameras are supported"))?;
+
+        // glTF cameras look down their local -Z with +Y up.
+        let origin = transform_point(transform, Point3::new(0.0, 0.0, 0.0));
+        let forward = transform_vector(transform, Vec3::new(0.0, 0.0, -1.0));
+        let up = transform_vector(transform, Vec3::new(0.0, 1.0, 0.0));
+        if forward.near_zero() || forward.cross(up).near_zero() {
+            return Err(invalid(&location, "node transform collapses the camera"));
+        }
+
+        Ok(Camera::look_at(
+            origin,
+            origin + forward,
+            up,
+            yfov.to_degrees(),
+            self.aspect_ratio,
+        ))
+    }
+
+    /// Reads an accessor used as a mesh attribute, checking its type against the
+    /// component counts the attribute allows.
+    fn read_attribute(
+        &self,
+        accessor: usize,
+        allowed: &[usize],
+        name: &str,
+        location: &str,
+    ) -> Result<(Vec<f64>, usize), GltfError> {
+        let (values, components) = self.read_accessor(accessor)?;
+        if !allowed.contains(&components) {
+            return Err(invalid(
+                location,
+                &format!("{} accessor has the wrong type", name),
+            ));
+        }
+        Ok((values, components))
+    }
+
+    /// Reads every element of an accessor as `f64`, returning the flattened values and
+    /// the number of components per element.
+    fn read_accessor(&self, index: usize) -> Result<(Vec<f64>, usize), GltfError> {
+        let location = format!("accessors[{}]", index);
+        let accessor = array(self.document, "accessors")
+            .get(index)
+            .ok_or_else(|| invalid(&location, "accessor index out of range"))?;
+        if accessor.get("sparse").is_some() {
+            return Err(invalid(&location, "sparse accessors are not supported"));
+        }
+
+        let count = accessor
+            .get("count")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| invalid(&location, "missing count"))? as usize;
+        let components = match accessor.get("type").and_then(Value::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            _ => return Err(invalid(&location, "unsupported accessor type")),
+        };
+        let component_type = accessor
+            .get("componentType")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| invalid(&location, "missing componentType"))?;
+        let (component_size, max_value) = match component_type {
+            5120 => (1, i8::MAX as f64),
+            5121 => (1, u8::MAX as f64),
+            5122 => (2, i16::MAX as f64),
+            5123 => (2, u16::MAX as f64),
+            5125 => (4, u32::MAX as f64),
+            5126 => (4, 1.0),
+            _ => return Err(invalid(&location, "unsupported componentType")),
+        };
+        let normalized = accessor
+            .get("normalized")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let view_index = match accessor.get("bufferView").and_then(Value::as_u64) {
+            Some(view) => view as usize,
+            // An accessor without a buffer view is all zeros.
+            None => {
+                let length = count
+                    .checked_mul(components)
+                    .ok_or_else(|| invalid(&location, "count is too large"))?;
+                return Ok((vec![0.0; length], components));
+            }
+        };
+        let view_location = format!("bufferViews[{}]", view_index);
+        let view = array(self.document, "bufferViews")
+            .get(view_index)
+            .ok_or_else(|| invalid(&location, "bufferView index out of range"))?;
+        let buffer = view
+            .get("buffer")
+            .and_then(Value::as_u64)
+            .and_then(|buffer| self.buffers.get(buffer as usize))
+            .ok_or_else(|| invalid(&view_location, "buffer index out of range"))?;
+        let view_offset = view.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let view_length =
+            view.get("byteLength")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| invalid(&view_location, "missing byteLength"))? as usize;
+        let data = view_offset
+            .checked_add(view_length)
+            .and_then(|view_end| buffer.get(view_offset..view_end))
+            .ok_or_else(|| invalid(&view_location, "view extends past end of buffer"))?;
+
+        let element_size = component_size * components;
+        let stride = view
+            .get("byteStride")
+            .and_then(Value::as_u64)
+            .map_or(element_size, |stride| stride as usize);
+        if stride < element_size {
+            return Err(invalid(
+                &view_location,
+                "byteStride is smaller than an element",
+            ));
+        }
+        let offset = accessor
+            .get("byteOffset")
+            .and_then(Value::as_u64)
+            .unwrap_or(0) as usize;
+
+        // Check the last element up front so `count` is bounded by the view's length
+        // before anything is allocated for it.
+        let end = match count.checked_sub(1) {
+            Some(last) => last
+                .checked_mul(stride)
+                .and_then(|start| start.checked_add(offset))
+                .and_then(|start| start.checked_add(element_size)),
+            None => Some(0),
+        };
+        if end.is_none_or(|end| end > data.len()) {
+            return Err(invalid(&location, "accessor extends past end of view"));
+        }
+
+        let mut values = Vec::with_capacity(count * components);
+        for element in 0..count {
+            let start = offset + element * stride;
+            let bytes = data
+                .get(start..start + element_size)
+                .ok_or_else(|| invalid(&location, "accessor extends past end of view"))?;
+            for component in bytes.chunks_exact(component_size) {
+                let value = match component_type {
+                    5120 => component[0] as i8 as f64,
+                    5121 => component[0] as f64,
+                    5122 => i16::from_le_bytes([component[0], component[1]]) as f64,
+                    5123 => u16::from_le_bytes([component[0], component[1]]) as f64,
+                    5125 => {
+                        u32::from_le_bytes([component[0], component[1], component[2], component[3]])
+                            as f64
+                    }
+                    _ => {
+                        f32::from_le_bytes([component[0], component[1], component[2], component[3]])
+                            as f64
+                    }
+                };
+                values.push(if normalized && component_type != 5126 {
+                    (value / max_value).max(-1.0)
+                } else {
+                    value
+                });
+            }
+        }
+
+        Ok((values, components))
+    }
+}
+
+fn base_color(material: &Value) -> Color {
+    material
+        .get("pbrMetallicRoughness")
+        .and_then(|pbr| pbr.get("baseColorFactor"))
+        .and_then(Value::as_array)
+        .map(|c| {
+            let channel = |i: usize| c.get(i).and_then(Value::as_f64).unwrap_or(1.0);
+            Color::new(channel(0), channel(1), channel(2))
+        })
+        .unwrap_or_else(|| Color::new(1.0, 1.0, 1.0))
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+fn indices(value: &Value, key: &str, location: &str) -> Result<Vec<usize>, GltfError> {
+    array(value, key)
+        .iter()
+        .map(|index| {
+            index
+                .as_u64()
+                .map(|index| index as usize)
+                .ok_or_else(|| invalid(location, &format!("'{}' must contain indices", key)))
+        })
+        .collect()
+}
+
+fn invalid(location: &str, message: &str) -> GltfError {
+    GltfError::Invalid {
+        location: location.to_string(),
+        message: message.to_string(),
+    }
+}
+
+type Matrix = [[f64; 4]; 4];
+
+const IDENTITY: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn local_transform(node: &Value, location: &str) -> Result<Matrix, GltfError> {
+    let numbers = |key: &str, len: usize| -> Result<Option<Vec<f64>>, GltfError> {
+        match node.get(key) {
+            None => Ok(None),
+            Some(value) => {
+                let values: Option<Vec<f64>> = value
+                    .as_array()
+                    .map(|items| items.iter().filter_map(Value::as_f64).collect());
+                match values {
+                    Some(values) if values.len() == len => Ok(Some(values)),
+                    _ => Err(invalid(
+                        location,
+                        &format!("'{}' must be {} numbers", key, len),
+                    )),
+                }
+            }
+        }
+    };
+
+    if let Some(m) = numbers("matrix", 16)? {
+        // glTF matrices are column-major.
+        let mut matrix = IDENTITY;
+        for (column, values) in m.chunks(4).enumerate() {
+            for (row, value) in values.iter().enumerate() {
+                matrix[row][column] = *value;
+            }
+        }
+        return Ok(matrix);
+    }
+
+    let t = numbers("translation", 3)?.unwrap_or_else(|| vec![0.0, 0.0, 0.0]);
+    let r = numbers("rotation", 4)?.unwrap_or_else(|| vec![0.0, 0.0, 0.0, 1.0]);
+    let s = numbers("scale", 3)?.unwrap_or_else(|| vec![1.0, 1.0, 1.0]);
+    let (x, y, z, w) = (r[0], r[1], r[2], r[3]);
+
+    let rotation = [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ];
+
+    let mut matrix = IDENTITY;
+    for row in 0..3 {
+        for column in 0..3 {
+            matrix[row][column] = rotation[row][column] * s[column];
+        }
+        matrix[row][3] = t[row];
+    }
+
+    Ok(matrix)
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut result = [[0.0; 4]; 4];
+    for (row, result_row) in result.iter_mut().enumerate() {
+        for (column, value) in result_row.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[row][k] * b[k][column]).sum();
+        }
+    }
+    result
+}
+
+fn transform_point(m: &Matrix, p: Point3) -> Point3 {
+    transform_vector(m, p) + Vec3::new(m[0][3], m[1][3], m[2][3])
+}
+
+fn transform_vector(m: &Matrix, v: Vec3) -> Vec3 {
+    Vec3::new(
+        m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+        m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+        m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+    )
+}
+
+/// Inverse transpose of the upper 3x3 block, used to transform normals. Only the
+/// direction matters so the adjugate is used without dividing by the determinant.
+fn normal_matrix(m: &Matrix) -> Matrix {
+    let cofactor =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let determinant_sign = {
+        let determinant = m[0][0] * cofactor(1, 2, 1, 2) - m[0][1] * cofactor(1, 2, 0, 2)
+            + m[0][2] * cofactor(1, 2, 0, 1);
+        if determinant < 0.0 {
+            -1.0
+        } else {
+            1.0
+        }
+    };
+
+    let mut result = IDENTITY;
+    result[0][0] = cofactor(1, 2, 1, 2) * determinant_sign;
+    result[0][1] = -cofactor(1, 2, 0, 2) * determinant_sign;
+    result[0][2] = cofactor(1, 2, 0, 1) * determinant_sign;
+    result[1][0] = -cofactor(0, 2, 1, 2) * determinant_sign;
+    result[1][1] = cofactor(0, 2, 0, 2) * determinant_sign;
+    result[1][2] = -cofactor(0, 2, 0, 1) * determinant_sign;
+    result[2][0] = cofactor(0, 1, 1, 2) * determinant_sign;
+    result[2][1] = -cofactor(0, 1, 0, 2) * determinant_sign;
+    result[2][2] = cofactor(0, 1, 0, 1) * determinant_sign;
+    result
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    };
+
+    let symbols: Vec<u8> = encoded
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+        .map(value)
+        .collect::<Option<_>>()?;
+    if symbols.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut output = Vec::with_capacity(symbols.len() * 3 / 4);
+    for group in symbols.chunks(4) {
+        let mut bits = 0u32;
+        for (i, symbol) in group.iter().enumerate() {
+            bits |= (*symbol as u32) << (18 - 6 * i);
+        }
+        let bytes = [(bits >> 16) as u8, (bits >> 8) as u8, bits as u8];
+        output.extend_from_slice(&bytes[..group.len() - 1]);
+    }
+
+    Some(output)
+}
+
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(value)) => {
+                output.push(value);
+                i += 3;
+            }
+            (byte, _) => {
+                output.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gltf::{decode_base64, parse_gltf, GltfError};
+    use crate::hittable::Hittable;
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut output = String::new();
+        for group in bytes.chunks(3) {
+            let mut bits = 0u32;
+            for (i, byte) in group.iter().enumerate() {
+                bits |= (*byte as u32) << (16 - 8 * i);
+            }
+            for i in 0..4 {
+                if i <= group.len() {
+                    output.push(ALPHABET[(bits >> (18 - 6 * i)) as usize & 63] as char);
+                } else {
+                    output.push('=');
+                }
+            }
+        }
+        output
+    }
+
+    /// A single triangle in the z = 0 plane plus u16 indices, padded to 4 bytes.
+    fn triangle_buffer() -> Vec<u8> {
+        let mut bytes = vec![];
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for index in [0u16, 1, 2, 0] {
+            bytes.extend_from_slice(&index.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn document(buffer: &str) -> String {
+        format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "scene": 0,
+                "scenes": [{{ "nodes": [0, 2] }}],
+                "nodes": [
+                    {{ "translation": [0, 0, -5], "children": [1] }},
+                    {{ "mesh": 0, "scale": [2, 2, 2] }},
+                    {{ "camera": 0, "translation": [0, 0, 3] }}
+                ],
+                "cameras": [{{ "type": "perspective", "perspective": {{ "yfov": 1.5707963267948966, "znear": 0.1 }} }}],
+                "materials": [{{ "pbrMetallicRoughness": {{ "baseColorFactor": [1, 0, 0, 1], "metallicFactor": 1, "roughnessFactor": 0.25 }} }}],
+                "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": 0 }}] }}],
+                "buffers": [{}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}
+                ],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+                    {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+                ]
+            }}"#,
+            buffer
+        )
+    }
+
+    fn check_scene(bytes: &[u8]) {
+        let scene = parse_gltf(bytes, None, 1.0).unwrap();
+
+        assert_eq!(scene.objects.objects.len(), 1);
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = scene.objects.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert_eq!(record.t, 5.0);
+
+        let miss = Ray::new(Vec3::new(1.5, 1.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(scene.objects.hit(&miss, 0.001, f64::INFINITY).is_none());
+
+        let camera = scene.camera.unwrap();
+        assert_eq!(camera.origin, Vec3::new(0.0, 0.0, 3.0));
+        assert!((camera.horizontal - Vec3::new(2.0, 0.0, 0.0)).near_zero());
+        let centre = camera.get_ray(0.5, 0.5);
+        assert!((centre.direction - Vec3::new(0.0, 0.0, -1.0)).near_zero());
+    }
+
+    #[test]
+    fn parse_embedded_gltf() {
+        let uri = format!(
+            r#"{{ "byteLength": 44, "uri": "data:application/octet-stream;base64,{}" }}"#,
+            encode_base64(&triangle_buffer())
+        );
+        check_scene(document(&uri).as_bytes());
+    }
+
+    #[test]
+    fn parse_glb() {
+        let mut json = document(r#"{ "byteLength": 44 }"#).into_bytes();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let binary = triangle_buffer();
+
+        let mut glb = vec![];
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&((12 + 8 + json.len() + 8 + binary.len()) as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&0x4E4F534Au32.to_le_bytes());
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(binary.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&0x004E4942u32.to_le_bytes());
+        glb.extend_from_slice(&binary);
+
+        check_scene(&glb);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let missing_buffer = parse_gltf(document(r#"{ "byteLength": 44 }"#).as_bytes(), None, 1.0);
+        assert!(
+            matches!(missing_buffer, Err(GltfError::Invalid { location, .. }) if location == "buffers[0]")
+        );
+
+        let uri = format!(
+            r#"{{ "byteLength": 44, "uri": "data:application/octet-stream;base64,{}" }}"#,
+            encode_base64(&triangle_buffer())
+        );
+        let invalid_at = |source: String, expected: &str| {
+            let result = parse_gltf(source.as_bytes(), None, 1.0);
+            let error = result.err().map(|error| error.to_string());
+            assert_eq!(error.as_deref(), Some(expected));
+        };
+        invalid_at(
+            document(&uri).replace(
+                r#""scale": [2, 2, 2]"#,
+                r#""scale": [2, 2, 2], "children": [0]"#,
+            ),
+            "nodes[0]: node hierarchy contains a cycle",
+        );
+        invalid_at(
+            document(&uri).replace(
+                r#""count": 3, "type": "VEC3""#,
+                r#""count": 3, "type": "VEC2""#,
+            ),
+            "meshes[0].primitives[0]: POSITION accessor has the wrong type",
+        );
+        invalid_at(
+            document(&uri).replace(
+                r#""byteOffset": 36"#,
+                r#""byteOffset": 18446744073709551615"#,
+            ),
+            "bufferViews[1]: view extends past end of buffer",
+        );
+        invalid_at(
+            document(&uri).replace(
+                r#""count": 3, "type": "SCALAR""#,
+                r#""count": 4611686018427387904, "type": "SCALAR""#,
+            ),
+            "accessors[1]: accessor extends past end of view",
+        );
+
+        invalid_at(
+            document(&uri).replace(
+                r#""camera": 0, "translation": [0, 0, 3]"#,
+                r#""camera": 0, "translation": [0, 0, 3], "scale": [0, 0, 0]"#,
+            ),
+            "cameras[0]: node transform collapses the camera",
+        );
+
+        // An accessor without a buffer view reads as zeros.
+        invalid_at(
+            document(&uri)
+                .replace(r#""POSITION": 0"#, r#""POSITION": 0, "NORMAL": 2"#)
+                .replace(
+                    r#""type": "SCALAR" }"#,
+                    r#""type": "SCALAR" }, { "componentType": 5126, "count": 3, "type": "VEC3" }"#,
+                ),
+            "meshes[0].primitives[0]: NORMAL has zero length",
+        );
+
+        let not_json = parse_gltf(b"{ nope", None, 1.0);
+        assert!(matches!(not_json, Err(GltfError::Json(_))));
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(decode_base64("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(decode_base64("aGk=").unwrap(), b"hi");
+        assert!(decode_base64("a").is_none());
+    }
+}
@@ -1,17 +1,56 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
 use crate::material::Material;
+use crate::random::random_double;
 use crate::ray::Ray;
-use crate::sphere::Sphere;
-use crate::vec3::Vec3;
+use crate::vec3::{Color, Point3, Vec3};
+
+pub trait Hittable: Send + Sync {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>>;
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// True for emitters the renderer can sample directly through `pdf_value` and
+    /// `random`. Scenes collect these into their light list.
+    fn is_light(&self) -> bool {
+        false
+    }
+
+    /// Density, with respect to solid angle around `origin`, of `random` returning
+    /// `direction`.
+    fn pdf_value(&self, _origin: Point3, _direction: Vec3) -> f64 {
+        0.0
+    }
 
-pub trait Hittable {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+    /// A direction from `origin` towards a random point of the object.
+    fn random(&self, _origin: Point3) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
 }
 
+#[derive(Clone, Copy)]
 pub struct HitRecord<'material> {
     pub p: Vec3,
+    /// `p` relative to the object, so object space textures move with it. Geometry
+    /// stored in world coordinates leaves it equal to `p`.
+    pub local: Point3,
+    /// Shading normal, facing the incoming ray. Smooth mesh normals and bump maps
+    /// tilt it away from `geometric_normal`.
     pub normal: Vec3,
+    /// Normal of the actual surface, facing the incoming ray.
+    pub geometric_normal: Vec3,
+    /// Derivatives of `p` along `u` and `v`, zero where a shape has no
+    /// parameterization. They orient tangent space for normal maps.
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
     pub t: f64,
     pub front_face: bool,
+    pub barycentric: (f64, f64),
+    /// Surface coordinates for texture lookups.
+    pub u: f64,
+    pub v: f64,
+    /// Interpolated vertex color of meshes that have them, see `VertexColor`.
+    pub color: Option<Color>,
     pub material: &'material Material,
 }
 
@@ -19,9 +58,17 @@ impl<'material> HitRecord<'material> {
     pub fn new_empty() -> HitRecord<'material> {
         HitRecord {
             p: Vec3::new(0.0, 0.0, 0.0),
+            local: Point3::new(0.0, 0.0, 0.0),
             normal: Vec3::new(0.0, 0.0, 0.0),
+            geometric_normal: Vec3::new(0.0, 0.0, 0.0),
+            tangent: Vec3::new(0.0, 0.0, 0.0),
+            bitangent: Vec3::new(0.0, 0.0, 0.0),
             t: 0.0,
             front_face: false,
+            barycentric: (0.0, 0.0),
+            u: 0.0,
+            v: 0.0,
+            color: None,
             material: &Material::None,
         }
     }
@@ -33,12 +80,19 @@ impl<'material> HitRecord<'material> {
         } else {
             -outward_normal
         };
+        self.geometric_normal = self.normal;
+    }
+
+    /// Whether `direction` leaves the surface on the side the ray came from. Shading
+    /// normals can point below the surface, so this goes by the geometric normal.
+    pub fn same_side(&self, direction: Vec3) -> bool {
+        direction.dot(self.geometric_normal) > 0.0
     }
 }
 
 #[derive(Default)]
 pub struct HittableList {
-    pub objects: Vec<Sphere>,
+    pub objects: Vec<Arc<dyn Hittable>>,
 }
 
 impl HittableList {
@@ -50,31 +104,76 @@ impl HittableList {
         self.objects = vec![];
     }
 
-    pub fn add(&mut self, object: Sphere) {
+    pub fn add<H: Hittable + 'static>(&mut self, object: H) {
+        self.objects.push(Arc::new(object));
+    }
+
+    pub fn add_shared(&mut self, object: Arc<dyn Hittable>) {
         self.objects.push(object);
     }
 }
 
 impl Hittable for HittableList {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let mut closest_so_far = t_max;
+        let mut hit_record = None;
 
         for object in self.objects.iter() {
             if let Some(record) = object.hit(r, t_min, closest_so_far) {
                 closest_so_far = record.t;
-            } else {
-                return None;
+                hit_record = Some(record);
             }
         }
 
-        None
+        hit_record
+    }
+
+    /// A list of lights, such as the sides of an emissive box, is one light sampled
+    /// by picking a member uniformly.
+    fn is_light(&self) -> bool {
+        !self.objects.is_empty() && self.objects.iter().all(|object| object.is_light())
+    }
+
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        if self.objects.is_empty() {
+            return 0.0;
+        }
+
+        let sum: f64 = self
+            .objects
+            .iter()
+            .map(|object| object.pdf_value(origin, direction))
+            .sum();
+        sum / self.objects.len() as f64
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        if self.objects.is_empty() {
+            return Vec3::new(1.0, 0.0, 0.0);
+        }
+
+        let index = (random_double() * self.objects.len() as f64) as usize;
+        self.objects[index.min(self.objects.len() - 1)].random(origin)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut output_box: Option<Aabb> = None;
+        for object in self.objects.iter() {
+            let object_box = object.bounding_box()?;
+            output_box = Some(match output_box {
+                Some(existing) => Aabb::surrounding_box(existing, object_box),
+                None => object_box,
+            });
+        }
+
+        output_box
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        hittable::{HitRecord, HittableList},
+        hittable::{HitRecord, Hittable, HittableList},
         material::Material,
         ray::Ray,
         sphere::Sphere,
@@ -147,4 +246,28 @@ mod tests {
 
         assert_eq!(list.objects.len(), 0);
     }
+
+    #[test]
+    fn hit_hittable_list_returns_closest() {
+        let mut list = HittableList::new();
+        list.add(Sphere::new(Vec3::new(0.0, 0.0, -10.0), 1.0, Material::None));
+        list.add(Sphere::new(Vec3::new(0.0, 0.0, -5.0), 1.0, Material::None));
+        list.add(Sphere::new(Vec3::new(0.0, 10.0, -5.0), 1.0, Material::None));
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = list.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        assert_eq!(record.t, 4.0);
+        assert_eq!(record.p, Vec3::new(0.0, 0.0, -4.0));
+    }
+
+    #[test]
+    fn hit_hittable_list_miss() {
+        let mut list = HittableList::new();
+        list.add(Sphere::new(Vec3::new(0.0, 0.0, -5.0), 1.0, Material::None));
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+
+        assert!(list.hit(&ray, 0.001, f64::INFINITY).is_none());
+    }
 }
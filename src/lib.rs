@@ -1,6 +1,27 @@
+pub mod aabb;
+pub mod background;
+pub mod bump;
+pub mod bvh;
 pub mod camera;
+pub mod distribution;
+pub mod gltf;
 pub mod hittable;
+pub mod light;
 pub mod material;
+pub mod moving_sphere;
+pub mod noise;
+pub mod obj;
+pub mod onb;
+pub mod ply;
+pub mod presets;
+pub mod quad;
+pub mod random;
 pub mod ray;
+pub mod render;
+pub mod scene;
+pub mod scene_file;
 pub mod sphere;
+pub mod texture;
+pub mod tonemap;
+pub mod triangle;
 pub mod vec3;
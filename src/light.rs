@@ -0,0 +1,259 @@
+use std::f64::consts::PI;
+
+use crate::onb::Onb;
+use crate::random::random_double;
+use crate::vec3::{Color, Point3, Vec3};
+
+/// How the light of a point or spot light weakens with distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Falloff {
+    /// The physically correct `1 / d²`.
+    InverseSquare,
+    /// `1 / d`, reaching further than real lights do.
+    Linear,
+    /// The same brightness at any distance.
+    None,
+}
+
+impl Falloff {
+    pub fn from_name(name: &str) -> Option<Falloff> {
+        match name {
+            "inverse_square" => Some(Falloff::InverseSquare),
+            "linear" => Some(Falloff::Linear),
+            "none" => Some(Falloff::None),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Falloff::InverseSquare => "inverse_square",
+            Falloff::Linear => "linear",
+            Falloff::None => "none",
+        }
+    }
+
+    fn attenuation(&self, distance: f64) -> f64 {
+        match self {
+            Falloff::InverseSquare => 1.0 / (distance * distance),
+            Falloff::Linear => 1.0 / distance,
+            Falloff::None => 1.0,
+        }
+    }
+}
+
+/// Lights without geometry. No ray can hit them, so they are only seen through
+/// shadow rays from non-specular surfaces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Light {
+    /// Shines `intensity` equally in every direction.
+    Point {
+        position: Point3,
+        intensity: Color,
+        falloff: Falloff,
+    },
+    /// A point light shining along `direction`, at full intensity within
+    /// `inner_angle` degrees of it and fading smoothly to nothing at `outer_angle`.
+    Spot {
+        position: Point3,
+        direction: Vec3,
+        intensity: Color,
+        inner_angle: f64,
+        outer_angle: f64,
+        falloff: Falloff,
+    },
+    /// Parallel light travelling along `direction`, delivering `irradiance` to a
+    /// surface facing it. A non-zero `angular_diameter` in degrees softens shadows
+    /// like those of the sun.
+    Directional {
+        direction: Vec3,
+        irradiance: Color,
+        angular_diameter: f64,
+    },
+}
+
+/// Light reaching a point from a `Light`.
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    /// Unit vector towards the light.
+    pub direction: Vec3,
+    /// Distance to the light, infinite for directional lights.
+    pub distance: f64,
+    /// Irradiance on a surface facing the light.
+    pub irradiance: Color,
+}
+
+impl Light {
+    /// Light arriving at `p`, or `None` where the light does not reach.
+    pub fn sample(&self, p: Point3) -> Option<LightSample> {
+        match *self {
+            Light::Point {
+                position,
+                intensity,
+                falloff,
+            } => point_sample(p, position, intensity, falloff),
+            Light::Spot {
+                position,
+                direction,
+                intensity,
+                inner_angle,
+                outer_angle,
+                falloff,
+            } => {
+                let mut sample = point_sample(p, position, intensity, falloff)?;
+                let cosine = -sample.direction.dot(direction.unit_vector());
+                let cos_outer = outer_angle.to_radians().cos();
+                let cos_inner = inner_angle.min(outer_angle).to_radians().cos();
+                let edge = if cos_inner > cos_outer {
+                    smoothstep((cosine - cos_outer) / (cos_inner - cos_outer))
+                } else if cosine >= cos_outer {
+                    1.0
+                } else {
+                    0.0
+                };
+                if edge <= 0.0 {
+                    return None;
+                }
+
+                sample.irradiance *= edge;
+                Some(sample)
+            }
+            Light::Directional {
+                direction,
+                irradiance,
+                angular_diameter,
+            } => {
+                let towards = -direction.unit_vector();
+                let direction = if angular_diameter > 0.0 {
+                    // Uniform over the cone the disc covers.
+                    let cos_max = (angular_diameter / 2.0).to_radians().cos();
+                    let cos_theta = 1.0 - random_double() * (1.0 - cos_max);
+                    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                    let phi = 2.0 * PI * random_double();
+                    Onb::from_w(towards).local(Vec3::new(
+                        phi.cos() * sin_theta,
+                        phi.sin() * sin_theta,
+                        cos_theta,
+                    ))
+                } else {
+                    towards
+                };
+
+                Some(LightSample {
+                    direction,
+                    distance: f64::INFINITY,
+                    irradiance,
+                })
+            }
+        }
+    }
+}
+
+fn point_sample(
+    p: Point3,
+    position: Point3,
+    intensity: Color,
+    falloff: Falloff,
+) -> Option<LightSample> {
+    let offset = position - p;
+    let distance = offset.length();
+    if distance <= 0.0 {
+        return None;
+    }
+
+    Some(LightSample {
+        direction: offset / distance,
+        distance,
+        irradiance: intensity * falloff.attenuation(distance),
+    })
+}
+
+fn smoothstep(x: f64) -> f64 {
+    let x = x.clamp(0.0, 1.0);
+    x * x * (3.0 - 2.0 * x)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::light::{Falloff, Light};
+    use crate::random;
+    use crate::vec3::{Color, Point3, Vec3};
+
+    #[test]
+    fn point_light_falloff() {
+        let light = |falloff| Light::Point {
+            position: Point3::new(0.0, 4.0, 0.0),
+            intensity: Color::new(8.0, 8.0, 8.0),
+            falloff,
+        };
+        let origin = Point3::new(0.0, 0.0, 0.0);
+
+        let sample = light(Falloff::InverseSquare).sample(origin).unwrap();
+        assert_eq!(sample.direction, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(sample.distance, 4.0);
+        assert_eq!(sample.irradiance, Color::new(0.5, 0.5, 0.5));
+
+        let sample = light(Falloff::Linear).sample(origin).unwrap();
+        assert_eq!(sample.irradiance, Color::new(2.0, 2.0, 2.0));
+        let sample = light(Falloff::None).sample(origin).unwrap();
+        assert_eq!(sample.irradiance, Color::new(8.0, 8.0, 8.0));
+
+        assert_eq!(Falloff::from_name("linear"), Some(Falloff::Linear));
+        assert_eq!(
+            Falloff::from_name(Falloff::None.name()),
+            Some(Falloff::None)
+        );
+        assert_eq!(Falloff::from_name("cubic"), None);
+    }
+
+    #[test]
+    fn spot_light_cone() {
+        let light = Light::Spot {
+            position: Point3::new(0.0, 1.0, 0.0),
+            direction: Vec3::new(0.0, -1.0, 0.0),
+            intensity: Color::new(1.0, 1.0, 1.0),
+            inner_angle: 20.0,
+            outer_angle: 40.0,
+            falloff: Falloff::None,
+        };
+        let at_angle = |degrees: f64| {
+            let p = Point3::new(degrees.to_radians().tan(), 0.0, 0.0);
+            light.sample(p).map_or(0.0, |sample| sample.irradiance.x)
+        };
+
+        assert_eq!(at_angle(0.0), 1.0);
+        assert_eq!(at_angle(19.0), 1.0);
+        assert!(at_angle(30.0) > 0.4 && at_angle(30.0) < 0.8);
+        assert!(at_angle(35.0) < at_angle(30.0));
+        assert_eq!(at_angle(41.0), 0.0);
+    }
+
+    #[test]
+    fn directional_light_cone() {
+        let direction = Vec3::new(0.0, -1.0, 0.0);
+        let hard = Light::Directional {
+            direction,
+            irradiance: Color::new(3.0, 3.0, 3.0),
+            angular_diameter: 0.0,
+        };
+        let sample = hard.sample(Point3::new(5.0, 0.0, 5.0)).unwrap();
+        assert_eq!(sample.direction, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(sample.distance, f64::INFINITY);
+
+        let soft = Light::Directional {
+            direction,
+            irradiance: Color::new(3.0, 3.0, 3.0),
+            angular_diameter: 10.0,
+        };
+        random::seed(1);
+        let cos_max = 5.0_f64.to_radians().cos();
+        let mut spread = 0.0_f64;
+        for _ in 0..200 {
+            let sample = soft.sample(Point3::new(0.0, 0.0, 0.0)).unwrap();
+            assert!((sample.direction.length() - 1.0).abs() < 1e-9);
+            assert!(sample.direction.y >= cos_max - 1e-9);
+            spread = spread.max(1.0 - sample.direction.y);
+        }
+        assert!(spread > 0.5 * (1.0 - cos_max));
+    }
+}
@@ -1,21 +1,66 @@
-use rand::Rng;
+use std::f64::consts::PI;
+use std::sync::Arc;
 
 use crate::{
+    bump::Bump,
     hittable::HitRecord,
+    onb::Onb,
+    random::random_double,
     ray::Ray,
+    texture::{SolidColor, Texture},
     vec3::{Color, Vec3},
 };
 
 pub trait Scatterable {
+    /// Samples an outgoing ray. For non-specular materials the attenuation equals
+    /// `eval / pdf` for the sampled direction.
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)>;
+
+    /// Light given off at the hit point, black for anything but light sources.
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// BSDF times the cosine term for light arriving along `direction` and leaving
+    /// back along `r_in`.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vec3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Solid angle density with which `scatter` picks `direction`.
+    fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _direction: Vec3) -> f64 {
+        0.0
+    }
+
+    /// Whether `scatter` follows a delta distribution that `eval` and `pdf` cannot
+    /// describe, as for mirrors and glass.
+    fn is_specular(&self) -> bool {
+        false
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Material {
     None,
     Lambertian(Lambertian),
     Metal(Metal),
     Dielectric(Dielectric),
+    DiffuseLight(DiffuseLight),
+}
+
+impl Material {
+    /// Tilts the shading normal of `rec` with the material's bump map, if it has one.
+    pub fn apply_bump(&self, rec: &mut HitRecord) {
+        let bump = match self {
+            Material::Lambertian(l) => &l.bump,
+            Material::Metal(m) => &m.bump,
+            Material::Dielectric(d) => &d.bump,
+            _ => return,
+        };
+        if let Some(bump) = bump {
+            bump.apply(rec);
+        }
+    }
 }
 
 impl Scatterable for Material {
@@ -25,69 +70,174 @@ impl Scatterable for Material {
             Material::Lambertian(l) => l.scatter(r_in, rec),
             Material::Metal(m) => m.scatter(r_in, rec),
             Material::Dielectric(d) => d.scatter(r_in, rec),
+            Material::DiffuseLight(l) => l.scatter(r_in, rec),
+        }
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        match self {
+            Material::DiffuseLight(l) => l.emitted(rec),
+            _ => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Color {
+        match self {
+            Material::Lambertian(l) => l.eval(r_in, rec, direction),
+            Material::Metal(m) => m.eval(r_in, rec, direction),
+            _ => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> f64 {
+        match self {
+            Material::Lambertian(l) => l.pdf(r_in, rec, direction),
+            Material::Metal(m) => m.pdf(r_in, rec, direction),
+            _ => 0.0,
+        }
+    }
+
+    fn is_specular(&self) -> bool {
+        match self {
+            Material::Metal(m) => m.is_specular(),
+            Material::Dielectric(d) => d.is_specular(),
+            _ => false,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Lambertian {
-    pub albedo: Color,
+    pub albedo: Arc<dyn Texture>,
+    pub bump: Option<Bump>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Lambertian {
-        Lambertian { albedo }
+        Lambertian::textured(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Lambertian {
+        Lambertian { albedo, bump: None }
     }
 }
 
 impl Scatterable for Lambertian {
-    fn scatter(&self, _r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
-        let mut scatter_dir = rec.normal + random_in_unit_sphere();
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
+        // Normal plus a point on the unit sphere is cosine distributed around the normal.
+        let mut scatter_dir = rec.normal + random_in_unit_sphere().unit_vector();
         if scatter_dir.near_zero() {
             scatter_dir = rec.normal;
         }
+        if !rec.same_side(scatter_dir) {
+            return None;
+        }
 
         let target = rec.p + scatter_dir;
-        let scattered = Ray::new(rec.p, target - rec.p);
-        let attenuation = self.albedo;
+        let scattered = Ray::with_time(rec.p, target - rec.p, r_in.time);
+        let attenuation = self.albedo.value(rec);
         Some((attenuation, scattered))
     }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Color {
+        self.albedo.value(rec) * self.pdf(r_in, rec, direction)
+    }
+
+    fn pdf(&self, _r_in: &Ray, rec: &HitRecord, direction: Vec3) -> f64 {
+        if !rec.same_side(direction) {
+            return 0.0;
+        }
+        rec.normal.dot(direction.unit_vector()).max(0.0) / PI
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Reflects around the mirror direction. A `fuzz` of zero is a perfect mirror,
+/// anything else a Phong lobe that widens with `fuzz`.
+#[derive(Debug, Clone)]
 pub struct Metal {
-    albedo: Color,
-    fuzz: f64,
+    pub albedo: Arc<dyn Texture>,
+    pub fuzz: f64,
+    pub bump: Option<Bump>,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: f64) -> Metal {
-        Metal { albedo, fuzz }
+        Metal::textured(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>, fuzz: f64) -> Metal {
+        Metal {
+            albedo,
+            fuzz,
+            bump: None,
+        }
+    }
+
+    /// Phong exponent of the lobe, the inverse of the `sqrt(2 / (Ns + 2))` roughness
+    /// used for OBJ materials.
+    pub fn exponent(&self) -> f64 {
+        let fuzz = self.fuzz.min(1.0);
+        2.0 / (fuzz * fuzz) - 2.0
     }
 }
 
 impl Scatterable for Metal {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<(Color, Ray)> {
         let reflected = r_in.direction.unit_vector().reflect(rec.normal);
-        let scattered = Ray::new(rec.p, reflected + self.fuzz * random_in_unit_sphere());
-        let attenuation = self.albedo;
-        if scattered.direction.dot(rec.normal) > 0.0 {
+        let direction = if self.is_specular() {
+            reflected
+        } else {
+            let cos_theta = random_double().powf(1.0 / (self.exponent() + 1.0));
+            let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+            let phi = 2.0 * PI * random_double();
+            Onb::from_w(reflected).local(Vec3::new(
+                phi.cos() * sin_theta,
+                phi.sin() * sin_theta,
+                cos_theta,
+            ))
+        };
+
+        let scattered = Ray::with_time(rec.p, direction, r_in.time);
+        let attenuation = self.albedo.value(rec);
+        if scattered.direction.dot(rec.normal) > 0.0 && rec.same_side(scattered.direction) {
             Some((attenuation, scattered))
         } else {
             None
         }
     }
+
+    fn eval(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> Color {
+        self.albedo.value(rec) * self.pdf(r_in, rec, direction)
+    }
+
+    fn pdf(&self, r_in: &Ray, rec: &HitRecord, direction: Vec3) -> f64 {
+        let direction = direction.unit_vector();
+        if self.is_specular() || rec.normal.dot(direction) <= 0.0 || !rec.same_side(direction) {
+            return 0.0;
+        }
+
+        let reflected = r_in.direction.unit_vector().reflect(rec.normal);
+        let cosine = reflected.dot(direction).max(0.0);
+        let exponent = self.exponent();
+        (exponent + 1.0) / (2.0 * PI) * cosine.powf(exponent)
+    }
+
+    fn is_specular(&self) -> bool {
+        self.fuzz <= 0.0
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Dielectric {
-    index_of_refraction: f64,
+    pub index_of_refraction: f64,
+    pub bump: Option<Bump>,
 }
 
 impl Dielectric {
     pub fn new(index_of_refraction: f64) -> Dielectric {
         Dielectric {
             index_of_refraction,
+            bump: None,
         }
     }
 
@@ -115,20 +265,48 @@ impl Scatterable for Dielectric {
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
 
-        let mut rng = rand::thread_rng();
-        let random_double: f64 = rng.gen_range(0.0..=1.0);
         let direction = if cannot_refract
-            || Dielectric::reflectance(cos_theta, refraction_ratio) > random_double
+            || Dielectric::reflectance(cos_theta, refraction_ratio) > random_double()
         {
             unit_dir.reflect(rec.normal)
         } else {
             unit_dir.refract(rec.normal, refraction_ratio)
         };
 
-        let scattered = Ray::new(rec.p, direction);
+        let scattered = Ray::with_time(rec.p, direction, r_in.time);
 
         Some((attenuation, scattered))
     }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
+}
+
+/// Emits `emit` from the front face of a surface and absorbs everything that hits it.
+#[derive(Debug, Clone, Copy)]
+pub struct DiffuseLight {
+    pub emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> DiffuseLight {
+        DiffuseLight { emit }
+    }
+}
+
+impl Scatterable for DiffuseLight {
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord) -> Option<(Color, Ray)> {
+        None
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color {
+        if rec.front_face {
+            self.emit
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        }
+    }
 }
 
 fn random_in_unit_sphere() -> Vec3 {
@@ -141,3 +319,101 @@ fn random_in_unit_sphere() -> Vec3 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::bump::Bump;
+    use crate::hittable::HitRecord;
+    use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal, Scatterable};
+    use crate::random;
+    use crate::ray::Ray;
+    use crate::texture::SolidColor;
+    use crate::vec3::{Color, Vec3};
+
+    #[test]
+    fn diffuse_light_emits_from_front_face() {
+        let light = Material::DiffuseLight(DiffuseLight::new(Color::new(4.0, 3.0, 2.0)));
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut record = HitRecord::new_empty();
+        record.set_face_normal(&ray, Vec3::new(0.0, 0.0, 1.0));
+
+        assert_eq!(light.emitted(&record), Color::new(4.0, 3.0, 2.0));
+        assert!(light.scatter(&ray, &record).is_none());
+
+        record.set_face_normal(&ray, Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(light.emitted(&record), Color::new(0.0, 0.0, 0.0));
+
+        let diffuse = Material::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        assert_eq!(diffuse.emitted(&record), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn scatter_matches_eval_and_pdf() {
+        let ray = Ray::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+        let mut record = HitRecord::new_empty();
+        record.set_face_normal(&ray, Vec3::new(0.0, 1.0, 0.0));
+        random::seed(3);
+
+        for material in [
+            Material::Lambertian(Lambertian::new(Color::new(0.5, 0.4, 0.3))),
+            Material::Metal(Metal::new(Color::new(0.9, 0.8, 0.7), 0.3)),
+        ] {
+            assert!(!material.is_specular());
+            for _ in 0..100 {
+                let Some((attenuation, scattered)) = material.scatter(&ray, &record) else {
+                    continue;
+                };
+                let pdf = material.pdf(&ray, &record, scattered.direction);
+                assert!(pdf > 0.0);
+                let ratio = material.eval(&ray, &record, scattered.direction) / pdf;
+                assert!((ratio - attenuation).length() < 1e-9);
+            }
+
+            // Nothing is reflected from below the surface.
+            let below = Vec3::new(0.3, -1.0, 0.0);
+            assert_eq!(material.pdf(&ray, &record, below), 0.0);
+        }
+
+        // The glossy lobe peaks around the mirror direction.
+        let metal = Material::Metal(Metal::new(Color::new(1.0, 1.0, 1.0), 0.3));
+        let mirror = metal.pdf(&ray, &record, Vec3::new(1.0, 1.0, 0.0));
+        let off = metal.pdf(&ray, &record, Vec3::new(0.2, 1.0, 0.0));
+        assert!(mirror > 10.0 * off);
+
+        assert!(Material::Metal(Metal::new(Color::new(1.0, 1.0, 1.0), 0.0)).is_specular());
+        assert!(Material::Dielectric(Dielectric::new(1.5)).is_specular());
+    }
+
+    #[test]
+    fn bump_tilts_shading_normal_only() {
+        let mut diffuse = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        diffuse.bump = Some(Bump::Normal {
+            map: Arc::new(SolidColor::new(Color::new(1.0, 0.5, 0.6))),
+            strength: 1.0,
+        });
+        let material = Material::Lambertian(diffuse);
+
+        let ray = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let mut record = HitRecord::new_empty();
+        record.set_face_normal(&ray, Vec3::new(0.0, 1.0, 0.0));
+        record.tangent = Vec3::new(1.0, 0.0, 0.0);
+        record.bitangent = Vec3::new(0.0, 0.0, -1.0);
+        material.apply_bump(&mut record);
+
+        assert!(record.normal.x > 0.5 && record.normal.y > 0.0);
+        assert_eq!(record.geometric_normal, Vec3::new(0.0, 1.0, 0.0));
+
+        // The tilted normal reaches below the surface, which still reflects nothing.
+        let below = Vec3::new(1.0, -0.1, 0.0);
+        assert!(record.normal.dot(below) > 0.0);
+        assert_eq!(material.pdf(&ray, &record, below), 0.0);
+        random::seed(4);
+        for _ in 0..100 {
+            if let Some((_, scattered)) = material.scatter(&ray, &record) {
+                assert!(scattered.direction.y > 0.0);
+            }
+        }
+    }
+}
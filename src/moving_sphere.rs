@@ -0,0 +1,127 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::sphere::{hit_sphere, sphere_box};
+use crate::vec3::Point3;
+
+/// Sphere whose center moves linearly from `center0` at `time0` to `center1` at
+/// `time1`. Rays are intersected against the sphere at their own time.
+pub struct MovingSphere {
+    pub center0: Point3,
+    pub center1: Point3,
+    pub time0: f64,
+    pub time1: f64,
+    pub radius: f64,
+    pub material: Material,
+}
+
+impl MovingSphere {
+    pub fn new(
+        center0: Point3,
+        center1: Point3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: Material,
+    ) -> MovingSphere {
+        MovingSphere {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material,
+        }
+    }
+
+    pub fn center(&self, time: f64) -> Point3 {
+        if self.time1 == self.time0 {
+            return self.center0;
+        }
+
+        self.center0
+            + ((time - self.time0) / (self.time1 - self.time0)) * (self.center1 - self.center0)
+    }
+}
+
+impl Hittable for MovingSphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_sphere(
+            self.center(r.time),
+            self.radius,
+            &self.material,
+            r,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let box0 = sphere_box(self.center(self.time0), self.radius);
+        let box1 = sphere_box(self.center(self.time1), self.radius);
+        Some(Aabb::surrounding_box(box0, box1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::Hittable;
+    use crate::material::Material;
+    use crate::moving_sphere::MovingSphere;
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+
+    fn moving_sphere() -> MovingSphere {
+        MovingSphere::new(
+            Vec3::new(0.0, 0.0, -5.0),
+            Vec3::new(4.0, 0.0, -5.0),
+            0.0,
+            1.0,
+            1.0,
+            Material::None,
+        )
+    }
+
+    #[test]
+    fn center() {
+        let sphere = moving_sphere();
+
+        assert_eq!(sphere.center(0.0), Vec3::new(0.0, 0.0, -5.0));
+        assert_eq!(sphere.center(0.5), Vec3::new(2.0, 0.0, -5.0));
+        assert_eq!(sphere.center(1.0), Vec3::new(4.0, 0.0, -5.0));
+    }
+
+    #[test]
+    fn hit_at_time() {
+        let sphere = moving_sphere();
+        let origin = Vec3::new(4.0, 0.0, 0.0);
+        let direction = Vec3::new(0.0, 0.0, -1.0);
+
+        assert!(sphere
+            .hit(
+                &Ray::with_time(origin, direction, 0.0),
+                0.001,
+                f64::INFINITY
+            )
+            .is_none());
+
+        let record = sphere
+            .hit(
+                &Ray::with_time(origin, direction, 1.0),
+                0.001,
+                f64::INFINITY,
+            )
+            .unwrap();
+        assert_eq!(record.t, 4.0);
+        assert_eq!(record.normal, Vec3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn bounding_box() {
+        let bounding_box = moving_sphere().bounding_box().unwrap();
+
+        assert_eq!(bounding_box.minimum, Vec3::new(-1.0, -1.0, -6.0));
+        assert_eq!(bounding_box.maximum, Vec3::new(5.0, 1.0, -4.0));
+    }
+}
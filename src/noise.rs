@@ -0,0 +1,206 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::vec3::{Point3, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+/// Random permutations of `0..POINT_COUNT`, one per axis, hashing lattice points.
+#[derive(Debug, Clone)]
+struct Lattice {
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Lattice {
+    fn new(rng: &mut StdRng) -> Lattice {
+        let mut permutation = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(rng);
+            p
+        };
+
+        Lattice {
+            perm_x: permutation(),
+            perm_y: permutation(),
+            perm_z: permutation(),
+        }
+    }
+
+    fn hash(&self, i: i64, j: i64, k: i64) -> usize {
+        let wrap = |n: i64| (n & (POINT_COUNT as i64 - 1)) as usize;
+        self.perm_x[wrap(i)] ^ self.perm_y[wrap(j)] ^ self.perm_z[wrap(k)]
+    }
+}
+
+/// Perlin gradient noise from the second book, built from a seed so the same seed
+/// always gives the same pattern.
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    lattice: Lattice,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                Vec3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .unit_vector()
+            })
+            .collect();
+
+        Perlin {
+            gradients,
+            lattice: Lattice::new(&mut rng),
+        }
+    }
+
+    /// Smooth noise in roughly `-1..1`, zero at every lattice point.
+    pub fn noise(&self, p: Point3) -> f64 {
+        let (i, j, k) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (u, v, w) = (p.x - i, p.y - j, p.z - k);
+        let (i, j, k) = (i as i64, j as i64, k as i64);
+
+        // Hermite smoothing hides the lattice.
+        let (uu, vv, ww) = (smooth(u), smooth(v), smooth(w));
+        let mut sum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = self.gradients[self.lattice.hash(i + di, j + dj, k + dk)];
+                    let (fi, fj, fk) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vec3::new(u - fi, v - fj, w - fk);
+                    sum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * gradient.dot(weight);
+                }
+            }
+        }
+        sum
+    }
+
+    /// Sum of `octaves` layers of absolute noise, each at twice the frequency and
+    /// half the weight of the one before.
+    pub fn turbulence(&self, p: Point3, octaves: usize) -> f64 {
+        let mut sum = 0.0;
+        let mut p = p;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            sum += weight * self.noise(p).abs();
+            weight *= 0.5;
+            p *= 2.0;
+        }
+        sum
+    }
+}
+
+/// Worley (cellular) noise: one random feature point in every unit cell.
+#[derive(Debug, Clone)]
+pub struct Worley {
+    offsets: Vec<Vec3>,
+    lattice: Lattice,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Worley {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let offsets = (0..POINT_COUNT)
+            .map(|_| Vec3::new(rng.gen(), rng.gen(), rng.gen()))
+            .collect();
+
+        Worley {
+            offsets,
+            lattice: Lattice::new(&mut rng),
+        }
+    }
+
+    /// Distance from `p` to the closest feature point, between 0 and about 1.
+    pub fn noise(&self, p: Point3) -> f64 {
+        let (i, j, k) = (p.x.floor() as i64, p.y.floor() as i64, p.z.floor() as i64);
+
+        let mut closest = f64::INFINITY;
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let (ci, cj, ck) = (i + di, j + dj, k + dk);
+                    let cell = Point3::new(ci as f64, cj as f64, ck as f64);
+                    let feature = cell + self.offsets[self.lattice.hash(ci, cj, ck)];
+                    closest = closest.min((feature - p).length_squared());
+                }
+            }
+        }
+        closest.sqrt()
+    }
+}
+
+fn smooth(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::noise::{Perlin, Worley};
+    use crate::vec3::Point3;
+
+    fn points() -> impl Iterator<Item = Point3> {
+        (0..200).map(|i| {
+            let t = i as f64 * 0.137;
+            Point3::new(t * 1.3 - 7.0, (t * 0.7).sin() * 5.0, t * 0.31)
+        })
+    }
+
+    #[test]
+    fn perlin_is_seeded_and_bounded() {
+        let perlin = Perlin::new(7);
+        let same = Perlin::new(7);
+        let other = Perlin::new(8);
+
+        let mut differs = false;
+        for p in points() {
+            let value = perlin.noise(p);
+            assert!((-1.0..=1.0).contains(&value));
+            assert_eq!(value, same.noise(p));
+            differs |= value != other.noise(p);
+        }
+        assert!(differs);
+
+        // Zero on the lattice, continuous in between.
+        assert_eq!(perlin.noise(Point3::new(3.0, -2.0, 5.0)), 0.0);
+        let p = Point3::new(0.3, 0.4, 0.5);
+        let nearby = Point3::new(0.3001, 0.4, 0.5);
+        assert!((perlin.noise(p) - perlin.noise(nearby)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn turbulence_adds_octaves() {
+        let perlin = Perlin::new(1);
+        for p in points() {
+            let one = perlin.turbulence(p, 1);
+            assert_eq!(one, perlin.noise(p).abs());
+            assert!(perlin.turbulence(p, 5) >= one);
+            assert!(perlin.turbulence(p, 5) < 2.0);
+        }
+    }
+
+    #[test]
+    fn worley_distances() {
+        let worley = Worley::new(3);
+        let mut smallest = f64::INFINITY;
+        for p in points() {
+            let value = worley.noise(p);
+            assert!((0.0..=3.0f64.sqrt()).contains(&value));
+            assert_eq!(value, Worley::new(3).noise(p));
+            smallest = smallest.min(value);
+        }
+        assert!(smallest < 0.5);
+    }
+}
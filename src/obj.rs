@@ -0,0 +1,600 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::triangle::{MeshData, TriangleMesh};
+use crate::vec3::{Color, Point3, Vec3};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Library {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            ObjError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            ObjError::Library {
+                path,
+                line,
+                message,
+            } => write!(f, "{} line {}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+/// A group of faces sharing an OBJ group/object name and material.
+pub struct ObjGroup {
+    pub name: String,
+    pub material_name: Option<String>,
+    pub mesh: TriangleMesh,
+}
+
+/// Loads an OBJ file and every MTL library it references, resolving library paths
+/// relative to the OBJ file.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<Vec<ObjGroup>, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut materials = HashMap::new();
+    for line in source.lines() {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("mtllib") {
+            continue;
+        }
+
+        for library in tokens {
+            let library_path = directory.join(library);
+            let library_source = read(&library_path)?;
+            let library_materials = parse_mtl(&library_source).map_err(|error| match error {
+                ObjError::Parse { line, message } => ObjError::Library {
+                    path: library_path.clone(),
+                    line,
+                    message,
+                },
+                other => other,
+            })?;
+            materials.extend(library_materials);
+        }
+    }
+
+    parse_obj(&source, &materials)
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|error| ObjError::Io {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+/// Parses MTL source into materials keyed by name.
+///
+/// MTL describes Phong-style surfaces, so the mapping is approximate:
+/// * A non-black emissive color `Ke` becomes a `DiffuseLight`.
+/// * `d < 1` or a refractive `illum` model (4, 6, 7, 9) becomes a `Dielectric` with
+///   index of refraction `Ni`.
+/// * `illum 3`, or a black `Kd` with a non-black `Ks`, becomes a `Metal` with albedo
+///   `Ks` and fuzz derived from the specular exponent `Ns`.
+/// * Everything else becomes a `Lambertian` with albedo `Kd`.
+pub fn parse_mtl(source: &str) -> Result<HashMap<String, Material>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlDescription)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+
+        if keyword == "newmtl" {
+            let name = tokens.collect::<Vec<_>>().join(" ");
+            if name.is_empty() {
+                return Err(parse_error(line_number, "newmtl without a name"));
+            }
+            if let Some((name, description)) = current.take() {
+                materials.insert(name, description.to_material());
+            }
+            current = Some((name, MtlDescription::default()));
+            continue;
+        }
+
+        let description = match current.as_mut() {
+            Some((_, description)) => description,
+            None => {
+                return Err(parse_error(
+                    line_number,
+                    &format!("'{}' before any newmtl", keyword),
+                ))
+            }
+        };
+
+        match keyword {
+            "Kd" => description.diffuse = parse_color(&mut tokens, line_number)?,
+            "Ks" => description.specular = parse_color(&mut tokens, line_number)?,
+            "Ke" => description.emissive = parse_color(&mut tokens, line_number)?,
+            "Ni" => description.index_of_refraction = parse_float(&mut tokens, line_number)?,
+            "Ns" => description.specular_exponent = parse_float(&mut tokens, line_number)?,
+            "d" => description.dissolve = parse_float(&mut tokens, line_number)?,
+            "Tr" => description.dissolve = 1.0 - parse_float(&mut tokens, line_number)?,
+            "illum" => {
+                let value = parse_float(&mut tokens, line_number)?;
+                description.illumination_model = value as u32;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some((name, description)) = current.take() {
+        materials.insert(name, description.to_material());
+    }
+
+    Ok(materials)
+}
+
+struct MtlDescription {
+    diffuse: Color,
+    specular: Color,
+    emissive: Color,
+    index_of_refraction: f64,
+    specular_exponent: f64,
+    dissolve: f64,
+    illumination_model: u32,
+}
+
+impl Default for MtlDescription {
+    fn default() -> MtlDescription {
+        MtlDescription {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::new(0.0, 0.0, 0.0),
+            emissive: Color::new(0.0, 0.0, 0.0),
+            index_of_refraction: 1.5,
+            specular_exponent: 0.0,
+            dissolve: 1.0,
+            illumination_model: 2,
+        }
+    }
+}
+
+impl MtlDescription {
+    fn to_material(&self) -> Material {
+        if self.emissive.length_squared() > 0.0 {
+            return Material::DiffuseLight(DiffuseLight::new(self.emissive));
+        }
+
+        let refractive = matches!(self.illumination_model, 4 | 6 | 7 | 9);
+        if self.dissolve < 1.0 || refractive {
+            return Material::Dielectric(Dielectric::new(self.index_of_refraction));
+        }
+
+        let has_specular = self.specular.length_squared() > 0.0;
+        if self.illumination_model == 3 || (self.diffuse.near_zero() && has_specular) {
+            let fuzz = (2.0 / (self.specular_exponent + 2.0)).sqrt().min(1.0);
+            return Material::Metal(Metal::new(self.specular, fuzz));
+        }
+
+        Material::Lambertian(Lambertian::new(self.diffuse))
+    }
+}
+
+#[derive(Default)]
+struct Chunk {
+    name: String,
+    material_name: Option<String>,
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    has_normals: bool,
+    has_uvs: bool,
+    indices: Vec<[usize; 3]>,
+    vertex_lookup: HashMap<(usize, Option<usize>, Option<usize>), usize>,
+}
+
+/// Parses OBJ source, triangulating polygons as fans. `materials` is used to resolve
+/// `usemtl` statements; `mtllib` statements are ignored here and handled by
+/// `load_obj`. Faces without a material get a light grey `Lambertian`.
+pub fn parse_obj(
+    source: &str,
+    materials: &HashMap<String, Material>,
+) -> Result<Vec<ObjGroup>, ObjError> {
+    let mut positions: Vec<Point3> = vec![];
+    let mut normals: Vec<Vec3> = vec![];
+    let mut uvs: Vec<(f64, f64)> = vec![];
+
+    let mut chunks: Vec<Chunk> = vec![];
+    let mut chunk_lookup: HashMap<(String, Option<String>), usize> = HashMap::new();
+    let mut group_name = String::from("default");
+    let mut material_name: Option<String> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+
+        match keyword {
+            "v" => {
+                let x = parse_float(&mut tokens, line_number)?;
+                let y = parse_float(&mut tokens, line_number)?;
+                let z = parse_float(&mut tokens, line_number)?;
+                positions.push(Point3::new(x, y, z));
+            }
+            "vn" => {
+                let x = parse_float(&mut tokens, line_number)?;
+                let y = parse_float(&mut tokens, line_number)?;
+                let z = parse_float(&mut tokens, line_number)?;
+                let normal = Vec3::new(x, y, z);
+                if normal.near_zero() {
+                    return Err(parse_error(line_number, "vertex normal has zero length"));
+                }
+                normals.push(normal);
+            }
+            "vt" => {
+                let u = parse_float(&mut tokens, line_number)?;
+                let v = match tokens.next() {
+                    Some(token) => parse_number(token, line_number)?,
+                    None => 0.0,
+                };
+                uvs.push((u, v));
+            }
+            "g" | "o" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                group_name = if name.is_empty() {
+                    String::from("default")
+                } else {
+                    name
+                };
+            }
+            "usemtl" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                if !materials.contains_key(&name) {
+                    return Err(parse_error(
+                        line_number,
+                        &format!("unknown material '{}'", name),
+                    ));
+                }
+                material_name = Some(name);
+            }
+            "f" => {
+                let mut corners = vec![];
+                for token in tokens {
+                    corners.push(parse_corner(
+                        token,
+                        line_number,
+                        positions.len(),
+                        uvs.len(),
+                        normals.len(),
+                    )?);
+                }
+                if corners.len() < 3 {
+                    return Err(parse_error(line_number, "face needs at least 3 vertices"));
+                }
+
+                let key = (group_name.clone(), material_name.clone());
+                let chunk_index = *chunk_lookup.entry(key).or_insert_with(|| {
+                    chunks.push(Chunk {
+                        name: group_name.clone(),
+                        material_name: material_name.clone(),
+                        has_normals: true,
+                        has_uvs: true,
+                        ..Chunk::default()
+                    });
+                    chunks.len() - 1
+                });
+                let chunk = &mut chunks[chunk_index];
+
+                let local: Vec<usize> = corners
+                    .iter()
+                    .map(|&corner| chunk.vertex(corner, &positions, &uvs, &normals))
+                    .collect();
+                for i in 1..local.len() - 1 {
+                    chunk.indices.push([local[0], local[i], local[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let default_material = Material::Lambertian(Lambertian::new(Color::new(0.8, 0.8, 0.8)));
+    let groups = chunks
+        .into_iter()
+        .map(|chunk| {
+            let material = chunk
+                .material_name
+                .as_ref()
+                .map_or_else(|| default_material.clone(), |name| materials[name].clone());
+            let mut data = MeshData::new(chunk.positions, chunk.indices, material);
+            if chunk.has_normals {
+                data.normals = Some(chunk.normals.iter().map(|n| n.unit_vector()).collect());
+            }
+            if chunk.has_uvs {
+                data.uvs = Some(chunk.uvs);
+            }
+
+            ObjGroup {
+                name: chunk.name,
+                material_name: chunk.material_name,
+                mesh: TriangleMesh::new(data),
+            }
+        })
+        .collect();
+
+    Ok(groups)
+}
+
+type Corner = (usize, Option<usize>, Option<usize>);
+
+impl Chunk {
+    fn vertex(
+        &mut self,
+        corner: Corner,
+        positions: &[Point3],
+        uvs: &[(f64, f64)],
+        normals: &[Vec3],
+    ) -> usize {
+        if let Some(&index) = self.vertex_lookup.get(&corner) {
+            return index;
+        }
+
+        let (position, uv, normal) = corner;
+        self.positions.push(positions[position]);
+        match uv {
+            Some(uv) => self.uvs.push(uvs[uv]),
+            None => self.has_uvs = false,
+        }
+        match normal {
+            Some(normal) => self.normals.push(normals[normal]),
+            None => self.has_normals = false,
+        }
+
+        let index = self.positions.len() - 1;
+        self.vertex_lookup.insert(corner, index);
+        index
+    }
+}
+
+/// Parses one `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner into zero-based indices.
+fn parse_corner(
+    token: &str,
+    line_number: usize,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+) -> Result<Corner, ObjError> {
+    let mut parts = token.split('/');
+    let position = resolve_index(parts.next(), position_count, line_number)?
+        .ok_or_else(|| parse_error(line_number, &format!("missing vertex in '{}'", token)))?;
+    let uv = resolve_index(parts.next(), uv_count, line_number)?;
+    let normal = resolve_index(parts.next(), normal_count, line_number)?;
+
+    Ok((position, uv, normal))
+}
+
+fn resolve_index(
+    token: Option<&str>,
+    count: usize,
+    line_number: usize,
+) -> Result<Option<usize>, ObjError> {
+    let token = match token {
+        Some(token) if !token.is_empty() => token,
+        _ => return Ok(None),
+    };
+
+    let value: i64 = token
+        .parse()
+        .map_err(|_| parse_error(line_number, &format!("invalid index '{}'", token)))?;
+    let resolved = if value > 0 {
+        value - 1
+    } else {
+        count as i64 + value
+    };
+
+    if value == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(parse_error(
+            line_number,
+            &format!("index {} out of range", value),
+        ));
+    }
+
+    Ok(Some(resolved as usize))
+}
+
+fn parse_number(token: &str, line_number: usize) -> Result<f64, ObjError> {
+    token
+        .parse()
+        .map_err(|_| parse_error(line_number, &format!("invalid number '{}'", token)))
+}
+
+fn parse_float<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    line_number: usize,
+) -> Result<f64, ObjError> {
+    match tokens.next() {
+        Some(token) => parse_number(token, line_number),
+        None => Err(parse_error(line_number, "missing number")),
+    }
+}
+
+fn parse_color<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    line_number: usize,
+) -> Result<Color, ObjError> {
+    let r = parse_float(tokens, line_number)?;
+    let g = parse_float(tokens, line_number)?;
+    let b = parse_float(tokens, line_number)?;
+    Ok(Color::new(r, g, b))
+}
+
+fn parse_error(line: usize, message: &str) -> ObjError {
+    ObjError::Parse {
+        line,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::material::Material;
+    use crate::obj::{parse_mtl, parse_obj, ObjError};
+    use crate::ray::Ray;
+    use crate::vec3::Vec3;
+
+    const QUAD: &str = "
+# unit quad
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+";
+
+    #[test]
+    fn parse_quad() {
+        let groups = parse_obj(QUAD, &HashMap::new()).unwrap();
+
+        assert_eq!(groups.len(), 1);
+        let data = groups[0].mesh.data();
+        assert_eq!(groups[0].name, "default");
+        assert_eq!(data.indices, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(data.positions.len(), 4);
+        assert_eq!(data.uvs.as_ref().unwrap()[2], (1.0, 1.0));
+        assert_eq!(data.normals.as_ref().unwrap()[0], Vec3::new(0.0, 0.0, 1.0));
+
+        let ray = Ray::new(Vec3::new(0.5, 0.5, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        assert!(groups[0].mesh.hit(&ray, 0.001, f64::INFINITY).is_some());
+    }
+
+    #[test]
+    fn parse_ngon_and_negative_indices() {
+        let source = "
+v 0 0 0
+v 1 0 0
+v 2 1 0
+v 1 2 0
+v 0 1 0
+f -5 -4 -3 -2 -1
+";
+        let groups = parse_obj(source, &HashMap::new()).unwrap();
+        let data = groups[0].mesh.data();
+
+        assert_eq!(data.indices, vec![[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+        assert!(data.normals.is_none());
+        assert!(data.uvs.is_none());
+    }
+
+    #[test]
+    fn parse_groups_and_materials() {
+        let materials = parse_mtl(
+            "
+newmtl red
+Kd 1 0 0
+
+newmtl gold
+Kd 0 0 0
+Ks 1 0.8 0.3
+Ns 1000
+
+newmtl glass
+Ni 1.33
+d 0.1
+
+newmtl lamp
+Kd 1 1 1
+Ke 10 9 8
+",
+        )
+        .unwrap();
+        assert!(matches!(
+            materials["lamp"],
+            Material::DiffuseLight(l) if l.emit == Vec3::new(10.0, 9.0, 8.0)
+        ));
+        let source = "
+v 0 0 0
+v 1 0 0
+v 0 1 0
+g first
+usemtl red
+f 1 2 3
+g second
+usemtl glass
+f 1 2 3
+usemtl gold
+f 3 2 1
+g first
+usemtl red
+f 3 2 1
+";
+        let groups = parse_obj(source, &materials).unwrap();
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].name, "first");
+        assert_eq!(groups[0].mesh.triangle_count(), 2);
+        let record = HitRecord::new_empty();
+        assert!(matches!(
+            &groups[0].mesh.data().material,
+            Material::Lambertian(l) if l.albedo.value(&record) == Vec3::new(1.0, 0.0, 0.0)
+        ));
+        assert_eq!(groups[1].material_name.as_deref(), Some("glass"));
+        assert!(matches!(
+            groups[1].mesh.data().material,
+            Material::Dielectric(_)
+        ));
+        assert!(matches!(groups[2].mesh.data().material, Material::Metal(_)));
+    }
+
+    #[test]
+    fn parse_errors_report_line() {
+        let out_of_range = parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n", &HashMap::new());
+        assert!(matches!(out_of_range, Err(ObjError::Parse { line: 3, .. })));
+
+        let bad_number = parse_obj("v 0 zero 0\n", &HashMap::new());
+        assert!(matches!(bad_number, Err(ObjError::Parse { line: 1, .. })));
+
+        let zero_normal = parse_obj("v 0 0 0\nvn 0 0 0\n", &HashMap::new());
+        assert!(matches!(zero_normal, Err(ObjError::Parse { line: 2, .. })));
+
+        let short_face = parse_obj("v 0 0 0\nv 1 0 0\n\nf 1 2\n", &HashMap::new());
+        assert!(matches!(short_face, Err(ObjError::Parse { line: 4, .. })));
+
+        let unknown_material = parse_obj("usemtl missing\n", &HashMap::new());
+        assert!(matches!(
+            unknown_material,
+            Err(ObjError::Parse { line: 1, .. })
+        ));
+
+        let orphan_property = parse_mtl("Kd 1 1 1\n");
+        assert!(matches!(
+            orphan_property,
+            Err(ObjError::Parse { line: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+use crate::vec3::Vec3;
+
+/// Orthonormal basis built around a direction `w`, used to turn directions sampled
+/// around +z into world space.
+#[derive(Debug, Clone, Copy)]
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    pub fn from_w(n: Vec3) -> Onb {
+        let w = n.unit_vector();
+        let a = if w.x.abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(a).unit_vector();
+        let u = v.cross(w);
+
+        Onb { u, v, w }
+    }
+
+    /// World space vector with coordinates `local` in this basis.
+    pub fn local(&self, local: Vec3) -> Vec3 {
+        local.x * self.u + local.y * self.v + local.z * self.w
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::onb::Onb;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn basis_is_orthonormal() {
+        for n in [
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-0.3, 0.8, 0.1),
+        ] {
+            let onb = Onb::from_w(n);
+
+            assert!((onb.w - n.unit_vector()).near_zero());
+            assert!((onb.u.length() - 1.0).abs() < 1e-12);
+            assert!((onb.v.length() - 1.0).abs() < 1e-12);
+            assert!(onb.u.dot(onb.v).abs() < 1e-12);
+            assert!(onb.u.dot(onb.w).abs() < 1e-12);
+            assert!((onb.u.cross(onb.v) - onb.w).near_zero());
+            assert!((onb.local(Vec3::new(0.0, 0.0, 1.0)) - onb.w).near_zero());
+        }
+    }
+}
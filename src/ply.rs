@@ -0,0 +1,634 @@
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+use crate::material::Material;
+use crate::triangle::{MeshData, TriangleMesh};
+use crate::vec3::{Color, Point3, Vec3};
+
+#[derive(Debug)]
+pub enum PlyError {
+    Io {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Header {
+        line: usize,
+        message: String,
+    },
+    Body {
+        element: String,
+        index: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlyError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            PlyError::Header { line, message } => {
+                write!(f, "header line {}: {}", line, message)
+            }
+            PlyError::Body {
+                element,
+                index,
+                message,
+            } => write!(f, "{} {}: {}", element, index, message),
+        }
+    }
+}
+
+impl std::error::Error for PlyError {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<ScalarType> {
+        match name {
+            "char" | "int8" => Some(ScalarType::Int8),
+            "uchar" | "uint8" => Some(ScalarType::UInt8),
+            "short" | "int16" => Some(ScalarType::Int16),
+            "ushort" | "uint16" => Some(ScalarType::UInt16),
+            "int" | "int32" => Some(ScalarType::Int32),
+            "uint" | "uint32" => Some(ScalarType::UInt32),
+            "float" | "float32" => Some(ScalarType::Float32),
+            "double" | "float64" => Some(ScalarType::Float64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, ScalarType::Float32 | ScalarType::Float64)
+    }
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar {
+        name: String,
+        ty: ScalarType,
+    },
+    List {
+        name: String,
+        count_ty: ScalarType,
+        item_ty: ScalarType,
+    },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar { name, .. } => name,
+            Property::List { name, .. } => name,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Loads a PLY file into a triangle mesh with the given material.
+pub fn load_ply(path: impl AsRef<Path>, material: Material) -> Result<TriangleMesh, PlyError> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|error| PlyError::Io {
+        path: path.to_path_buf(),
+        error,
+    })?;
+
+    Ok(TriangleMesh::new(parse_ply(&bytes, material)?))
+}
+
+/// Parses ASCII or binary (either endianness) PLY data. Vertex normals (`nx ny nz`),
+/// colors (`red green blue`) and texture coordinates (`u v`, `s t` or
+/// `texture_u texture_v`) are kept when present; polygons are triangulated as fans.
+pub fn parse_ply(bytes: &[u8], material: Material) -> Result<MeshData, PlyError> {
+    let (format, elements, body_start) = parse_header(bytes)?;
+    let body = &bytes[body_start..];
+
+    let mut reader = match format {
+        Format::Ascii => {
+            let text = std::str::from_utf8(body).map_err(|_| PlyError::Body {
+                element: String::from("body"),
+                index: 0,
+                message: String::from("ASCII body is not valid text"),
+            })?;
+            Reader::Ascii(text.split_ascii_whitespace())
+        }
+        Format::BinaryLittleEndian => Reader::Binary {
+            data: body,
+            position: 0,
+            big_endian: false,
+        },
+        Format::BinaryBigEndian => Reader::Binary {
+            data: body,
+            position: 0,
+            big_endian: true,
+        },
+    };
+
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut colors = vec![];
+    let mut uvs = vec![];
+    let mut indices = vec![];
+    let mut has_normals = false;
+    let mut has_colors = false;
+    let mut has_uvs = false;
+
+    for element in elements.iter() {
+        let find = |names: &[&str]| {
+            element
+                .properties
+                .iter()
+                .position(|property| names.contains(&property.name()))
+        };
+
+        match element.name.as_str() {
+            "vertex" => {
+                let xyz = [find(&["x"]), find(&["y"]), find(&["z"])];
+                let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+                let color = [
+                    find(&["red", "r", "diffuse_red"]),
+                    find(&["green", "g", "diffuse_green"]),
+                    find(&["blue", "b", "diffuse_blue"]),
+                ];
+                let uv = [
+                    find(&["u", "s", "texture_u", "texture_s"]),
+                    find(&["v", "t", "texture_v", "texture_t"]),
+                ];
+                if xyz.iter().any(Option::is_none) {
+                    return Err(PlyError::Body {
+                        element: element.name.clone(),
+                        index: 0,
+                        message: String::from("vertex element is missing x, y or z"),
+                    });
+                }
+                has_normals = normal.iter().all(Option::is_some);
+                has_colors = color.iter().all(Option::is_some);
+                has_uvs = uv.iter().all(Option::is_some);
+
+                for index in 0..element.count {
+                    let values = read_element(&mut reader, element, index)?;
+                    let scalar = |slot: Option<usize>| values[slot.unwrap()][0];
+
+                    positions.push(Point3::new(scalar(xyz[0]), scalar(xyz[1]), scalar(xyz[2])));
+                    if has_normals {
+                        let n = Vec3::new(scalar(normal[0]), scalar(normal[1]), scalar(normal[2]));
+                        if n.near_zero() {
+                            return Err(PlyError::Body {
+                                element: element.name.clone(),
+                                index,
+                                message: String::from("vertex normal has zero length"),
+                            });
+                        }
+                        normals.push(n.unit_vector());
+                    }
+                    if has_colors {
+                        let channel = |slot: Option<usize>| {
+                            let value = scalar(slot);
+                            match &element.properties[slot.unwrap()] {
+                                Property::Scalar { ty, .. } if ty.is_integer() => value / 255.0,
+                                _ => value,
+                            }
+                        };
+                        colors.push(Color::new(
+                            channel(color[0]),
+                            channel(color[1]),
+                            channel(color[2]),
+                        ));
+                    }
+                    if has_uvs {
+                        uvs.push((scalar(uv[0]), scalar(uv[1])));
+                    }
+                }
+            }
+            "face" => {
+                let list =
+                    find(&["vertex_indices", "vertex_index"]).ok_or_else(|| PlyError::Body {
+                        element: element.name.clone(),
+                        index: 0,
+                        message: String::from("face element has no vertex_indices list"),
+                    })?;
+
+                for index in 0..element.count {
+                    let values = read_element(&mut reader, element, index)?;
+                    let polygon = &values[list];
+                    if polygon.len() < 3 {
+                        return Err(PlyError::Body {
+                            element: element.name.clone(),
+                            index,
+                            message: String::from("face needs at least 3 vertices"),
+                        });
+                    }
+
+                    let mut corners = Vec::with_capacity(polygon.len());
+                    for &value in polygon.iter() {
+                        if value < 0.0 || value as usize >= positions.len() {
+                            return Err(PlyError::Body {
+                                element: element.name.clone(),
+                                index,
+                                message: format!("vertex index {} out of range", value),
+                            });
+                        }
+                        corners.push(value as usize);
+                    }
+                    for i in 1..corners.len() - 1 {
+                        indices.push([corners[0], corners[i], corners[i + 1]]);
+                    }
+                }
+            }
+            // Elements without properties take up no data, whatever their count.
+            _ if element.properties.is_empty() => {}
+            _ => {
+                for index in 0..element.count {
+                    read_element(&mut reader, element, index)?;
+                }
+            }
+        }
+    }
+
+    let mut data = MeshData::new(positions, indices, material);
+    if has_normals {
+        data.normals = Some(normals);
+    }
+    if has_colors {
+        data.colors = Some(colors);
+    }
+    if has_uvs {
+        data.uvs = Some(uvs);
+    }
+
+    Ok(data)
+}
+
+fn parse_header(bytes: &[u8]) -> Result<(Format, Vec<Element>, usize), PlyError> {
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    let mut offset = 0;
+    let mut line_number = 0;
+
+    loop {
+        let end = match bytes[offset..].iter().position(|&b| b == b'\n') {
+            Some(end) => offset + end,
+            None => return Err(header_error(line_number + 1, "missing end_header")),
+        };
+        let line = String::from_utf8_lossy(&bytes[offset..end]);
+        let line = line.trim();
+        offset = end + 1;
+        line_number += 1;
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if line_number == 1 {
+            if line != "ply" {
+                return Err(header_error(1, "missing 'ply' magic number"));
+            }
+            continue;
+        }
+
+        match tokens.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => {
+                        return Err(header_error(
+                            line_number,
+                            &format!("unknown format '{}'", name),
+                        ))
+                    }
+                });
+            }
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| {
+                    header_error(line_number, &format!("invalid element count '{}'", count))
+                })?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: vec![],
+                });
+            }
+            ["property", "list", count_ty, item_ty, name] => {
+                let property = Property::List {
+                    name: name.to_string(),
+                    count_ty: scalar_type(count_ty, line_number)?,
+                    item_ty: scalar_type(item_ty, line_number)?,
+                };
+                push_property(&mut elements, property, line_number)?;
+            }
+            ["property", ty, name] => {
+                let property = Property::Scalar {
+                    name: name.to_string(),
+                    ty: scalar_type(ty, line_number)?,
+                };
+                push_property(&mut elements, property, line_number)?;
+            }
+            ["end_header"] => break,
+            _ => {
+                return Err(header_error(
+                    line_number,
+                    &format!("unrecognised header line '{}'", line),
+                ))
+            }
+        }
+    }
+
+    let format = format.ok_or_else(|| header_error(line_number, "missing format line"))?;
+    Ok((format, elements, offset))
+}
+
+fn scalar_type(name: &str, line_number: usize) -> Result<ScalarType, PlyError> {
+    ScalarType::parse(name)
+        .ok_or_else(|| header_error(line_number, &format!("unknown property type '{}'", name)))
+}
+
+fn push_property(
+    elements: &mut [Element],
+    property: Property,
+    line_number: usize,
+) -> Result<(), PlyError> {
+    match elements.last_mut() {
+        Some(element) => {
+            element.properties.push(property);
+            Ok(())
+        }
+        None => Err(header_error(line_number, "property before any element")),
+    }
+}
+
+fn header_error(line: usize, message: &str) -> PlyError {
+    PlyError::Header {
+        line,
+        message: message.to_string(),
+    }
+}
+
+enum Reader<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary {
+        data: &'a [u8],
+        position: usize,
+        big_endian: bool,
+    },
+}
+
+impl Reader<'_> {
+    fn read(&mut self, ty: ScalarType) -> Result<f64, String> {
+        match self {
+            Reader::Ascii(tokens) => {
+                let token = tokens.next().ok_or("unexpected end of data")?;
+                token
+                    .parse()
+                    .map_err(|_| format!("invalid number '{}'", token))
+            }
+            Reader::Binary {
+                data,
+                position,
+                big_endian,
+            } => {
+                let size = ty.size();
+                let bytes = data
+                    .get(*position..*position + size)
+                    .ok_or("unexpected end of data")?;
+                *position += size;
+
+                let mut buffer = [0u8; 8];
+                buffer[..size].copy_from_slice(bytes);
+                if *big_endian {
+                    buffer[..size].reverse();
+                }
+
+                Ok(match ty {
+                    ScalarType::Int8 => buffer[0] as i8 as f64,
+                    ScalarType::UInt8 => buffer[0] as f64,
+                    ScalarType::Int16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    ScalarType::UInt16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+                    ScalarType::Int32 => {
+                        i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+                    }
+                    ScalarType::UInt32 => {
+                        u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+                    }
+                    ScalarType::Float32 => {
+                        f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+                    }
+                    ScalarType::Float64 => f64::from_le_bytes(buffer),
+                })
+            }
+        }
+    }
+}
+
+/// Reads one element, returning the values of each property in declaration order.
+/// Scalar properties produce a single value, lists produce one value per item.
+fn read_element(
+    reader: &mut Reader,
+    element: &Element,
+    index: usize,
+) -> Result<Vec<Vec<f64>>, PlyError> {
+    let body_error = |message: String| PlyError::Body {
+        element: element.name.clone(),
+        index,
+        message,
+    };
+
+    let mut values = Vec::with_capacity(element.properties.len());
+    for property in element.properties.iter() {
+        match property {
+            Property::Scalar { ty, .. } => {
+                values.push(vec![reader.read(*ty).map_err(body_error)?])
+            }
+            Property::List {
+                count_ty, item_ty, ..
+            } => {
+                let count = reader.read(*count_ty).map_err(body_error)? as usize;
+                // The count comes straight from the file, so let the reads bound it.
+                let mut items = vec![];
+                for _ in 0..count {
+                    items.push(reader.read(*item_ty).map_err(body_error)?);
+                }
+                values.push(items);
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::material::Material;
+    use crate::ply::{parse_ply, PlyError};
+    use crate::vec3::Vec3;
+
+    const ASCII_QUAD: &str = "ply
+format ascii 1.0
+comment a unit quad
+element vertex 4
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property uchar red
+property uchar green
+property uchar blue
+property float u
+property float v
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 0 0 1 255 0 0 0 0
+1 0 0 0 0 1 0 255 0 1 0
+1 1 0 0 0 1 0 0 255 1 1
+0 1 0 0 0 1 255 255 255 0 1
+4 0 1 2 3
+";
+
+    #[test]
+    fn parse_ascii() {
+        let data = parse_ply(ASCII_QUAD.as_bytes(), Material::None).unwrap();
+
+        assert_eq!(data.positions.len(), 4);
+        assert_eq!(data.positions[2], Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(data.indices, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(data.normals.unwrap()[1], Vec3::new(0.0, 0.0, 1.0));
+        assert_eq!(data.colors.unwrap()[1], Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(data.uvs.unwrap()[2], (1.0, 1.0));
+    }
+
+    fn binary_triangle(big_endian: bool) -> Vec<u8> {
+        let format = if big_endian {
+            "binary_big_endian"
+        } else {
+            "binary_little_endian"
+        };
+        let mut bytes = format!(
+            "ply\nformat {} 1.0\nelement vertex 3\nproperty float x\nproperty float y\n\
+             property double z\nelement edge 1\nproperty int vertex1\nproperty int vertex2\n\
+             element face 1\nproperty list uchar uint vertex_index\nend_header\n",
+            format
+        )
+        .into_bytes();
+
+        let vertices = [(0.0f32, 0.0f32, 1.0f64), (1.0, 0.0, 1.0), (0.0, 1.0, 1.0)];
+        for (x, y, z) in vertices {
+            if big_endian {
+                bytes.extend_from_slice(&x.to_be_bytes());
+                bytes.extend_from_slice(&y.to_be_bytes());
+                bytes.extend_from_slice(&z.to_be_bytes());
+            } else {
+                bytes.extend_from_slice(&x.to_le_bytes());
+                bytes.extend_from_slice(&y.to_le_bytes());
+                bytes.extend_from_slice(&z.to_le_bytes());
+            }
+        }
+        for value in [0i32, 1] {
+            if big_endian {
+                bytes.extend_from_slice(&value.to_be_bytes());
+            } else {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        bytes.push(3);
+        for index in [0u32, 1, 2] {
+            if big_endian {
+                bytes.extend_from_slice(&index.to_be_bytes());
+            } else {
+                bytes.extend_from_slice(&index.to_le_bytes());
+            }
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn parse_binary_little_endian() {
+        let data = parse_ply(&binary_triangle(false), Material::None).unwrap();
+
+        assert_eq!(data.positions[1], Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(data.indices, vec![[0, 1, 2]]);
+        assert!(data.normals.is_none());
+        assert!(data.colors.is_none());
+    }
+
+    #[test]
+    fn parse_binary_big_endian() {
+        let data = parse_ply(&binary_triangle(true), Material::None).unwrap();
+
+        assert_eq!(data.positions[2], Vec3::new(0.0, 1.0, 1.0));
+        assert_eq!(data.indices, vec![[0, 1, 2]]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let not_ply = parse_ply(b"obj\n", Material::None);
+        assert!(matches!(not_ply, Err(PlyError::Header { line: 1, .. })));
+
+        let bad_type = parse_ply(
+            b"ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n",
+            Material::None,
+        );
+        assert!(matches!(bad_type, Err(PlyError::Header { line: 4, .. })));
+
+        let mut truncated = binary_triangle(false);
+        truncated.truncate(truncated.len() - 2);
+        let truncated = parse_ply(&truncated, Material::None);
+        assert!(matches!(truncated, Err(PlyError::Body { index: 0, .. })));
+
+        let out_of_range = ASCII_QUAD.replace("4 0 1 2 3", "3 0 1 9");
+        let out_of_range = parse_ply(out_of_range.as_bytes(), Material::None);
+        assert!(matches!(out_of_range, Err(PlyError::Body { .. })));
+
+        let empty_element = ASCII_QUAD.replace(
+            "element face 1",
+            "element junk 18446744073709551615\nelement face 1",
+        );
+        let empty_element = parse_ply(empty_element.as_bytes(), Material::None).unwrap();
+        assert_eq!(empty_element.indices.len(), 2);
+
+        let zero_normal = ASCII_QUAD.replace("1 0 0 0 0 1 0 255", "1 0 0 0 0 0 0 255");
+        let zero_normal = parse_ply(zero_normal.as_bytes(), Material::None);
+        assert!(matches!(zero_normal, Err(PlyError::Body { index: 1, .. })));
+
+        let mut huge_list = b"ply\nformat binary_little_endian 1.0\nelement face 1\n\
+                              property list uint int vertex_indices\nend_header\n"
+            .to_vec();
+        huge_list.extend_from_slice(&u32::MAX.to_le_bytes());
+        let huge_list = parse_ply(&huge_list, Material::None);
+        assert!(matches!(huge_list, Err(PlyError::Body { index: 0, .. })));
+    }
+}
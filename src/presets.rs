@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::scene_file::{
+    BackgroundDescription, CameraDescription, ImageDescription, MaterialDescription,
+    ObjectDescription, SceneDescription,
+};
+use crate::vec3::{Color, Point3, Vec3};
+
+/// Names accepted by `preset`.
+pub const PRESETS: &[&str] = &[
+    "three-spheres",
+    "random-spheres",
+    "motion-blur",
+    "cornell-box",
+];
+
+/// Built-in demo scenes.
+pub fn preset(name: &str) -> Option<SceneDescription> {
+    match name {
+        "three-spheres" => Some(three_spheres()),
+        "random-spheres" => Some(random_spheres(false)),
+        "motion-blur" => Some(random_spheres(true)),
+        "cornell-box" => Some(cornell_box()),
+        _ => None,
+    }
+}
+
+fn image(width: u32, height: u32) -> ImageDescription {
+    ImageDescription {
+        width,
+        height,
+        samples_per_pixel: 100,
+        max_depth: 15,
+        seed: 0,
+    }
+}
+
+fn lambertian(albedo: Color) -> MaterialDescription {
+    MaterialDescription::Lambertian {
+        albedo: albedo.into(),
+        bump: None,
+    }
+}
+
+fn metal(albedo: Color, fuzz: f64) -> MaterialDescription {
+    MaterialDescription::Metal {
+        albedo: albedo.into(),
+        fuzz,
+        bump: None,
+    }
+}
+
+fn sphere(center: Point3, radius: f64, material: &str) -> ObjectDescription {
+    ObjectDescription::Sphere {
+        center,
+        radius,
+        material: material.to_string(),
+    }
+}
+
+fn quad(q: Point3, u: Vec3, v: Vec3, material: &str) -> ObjectDescription {
+    ObjectDescription::Quad {
+        q,
+        u,
+        v,
+        material: material.to_string(),
+    }
+}
+
+/// The ground, a diffuse, a glass and a metal sphere from the first book.
+fn three_spheres() -> SceneDescription {
+    let mut materials = BTreeMap::new();
+    materials.insert(
+        String::from("ground"),
+        lambertian(Color::new(0.8, 0.8, 0.0)),
+    );
+    materials.insert(
+        String::from("center"),
+        lambertian(Color::new(0.1, 0.2, 0.5)),
+    );
+    materials.insert(
+        String::from("glass"),
+        MaterialDescription::Dielectric {
+            index_of_refraction: 1.5,
+            bump: None,
+        },
+    );
+    materials.insert(String::from("gold"), metal(Color::new(0.8, 0.6, 0.2), 0.0));
+
+    SceneDescription {
+        image: image(400, 225),
+        camera: CameraDescription {
+            look_from: Point3::new(0.0, 0.0, 0.0),
+            look_at: Point3::new(0.0, 0.0, -1.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 90.0,
+            aperture: 0.0,
+            focus_dist: None,
+            shutter: (0.0, 0.0),
+        },
+        background: BackgroundDescription::default(),
+        materials,
+        objects: vec![
+            sphere(Point3::new(0.0, -100.5, -1.0), 100.0, "ground"),
+            sphere(Point3::new(0.0, 0.0, -1.0), 0.5, "center"),
+            sphere(Point3::new(-1.0, 0.0, -1.0), 0.5, "glass"),
+            sphere(Point3::new(1.0, 0.0, -1.0), 0.5, "gold"),
+            sphere(Point3::new(-1.0, 0.0, -1.0), -0.4, "glass"),
+        ],
+        lights: vec![],
+        base_directory: None,
+    }
+}
+
+/// The cover scene from the end of the first book. With `moving` the small diffuse
+/// spheres bounce upwards while the shutter is open.
+fn random_spheres(moving: bool) -> SceneDescription {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut materials = BTreeMap::new();
+    let mut objects = vec![];
+
+    materials.insert(
+        String::from("ground"),
+        lambertian(Color::new(0.5, 0.5, 0.5)),
+    );
+    materials.insert(
+        String::from("glass"),
+        MaterialDescription::Dielectric {
+            index_of_refraction: 1.5,
+            bump: None,
+        },
+    );
+    objects.push(sphere(Point3::new(0.0, -1000.0, 0.0), 1000.0, "ground"));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let center = Point3::new(
+                a as f64 + 0.9 * rng.gen::<f64>(),
+                0.2,
+                b as f64 + 0.9 * rng.gen::<f64>(),
+            );
+            if (center - Point3::new(4.0, 0.2, 0.0)).length() <= 0.9 {
+                continue;
+            }
+
+            let choose_material: f64 = rng.gen();
+            let name = format!("sphere_{}_{}", a, b);
+            if choose_material < 0.8 {
+                let albedo = Color::new(
+                    rng.gen::<f64>() * rng.gen::<f64>(),
+                    rng.gen::<f64>() * rng.gen::<f64>(),
+                    rng.gen::<f64>() * rng.gen::<f64>(),
+                );
+                materials.insert(name.clone(), lambertian(albedo));
+                if moving {
+                    objects.push(ObjectDescription::MovingSphere {
+                        center0: center,
+                        center1: center + Vec3::new(0.0, rng.gen_range(0.0..0.5), 0.0),
+                        time0: 0.0,
+                        time1: 1.0,
+                        radius: 0.2,
+                        material: name,
+                    });
+                } else {
+                    objects.push(sphere(center, 0.2, &name));
+                }
+            } else if choose_material < 0.95 {
+                let albedo = Color::new(
+                    rng.gen_range(0.5..1.0),
+                    rng.gen_range(0.5..1.0),
+                    rng.gen_range(0.5..1.0),
+                );
+                let fuzz = rng.gen_range(0.0..0.5);
+                materials.insert(name.clone(), metal(albedo, fuzz));
+                objects.push(sphere(center, 0.2, &name));
+            } else {
+                objects.push(sphere(center, 0.2, "glass"));
+            }
+        }
+    }
+
+    materials.insert(String::from("brown"), lambertian(Color::new(0.4, 0.2, 0.1)));
+    materials.insert(String::from("steel"), metal(Color::new(0.7, 0.6, 0.5), 0.0));
+    objects.push(sphere(Point3::new(0.0, 1.0, 0.0), 1.0, "glass"));
+    objects.push(sphere(Point3::new(-4.0, 1.0, 0.0), 1.0, "brown"));
+    objects.push(sphere(Point3::new(4.0, 1.0, 0.0), 1.0, "steel"));
+
+    SceneDescription {
+        image: image(400, 225),
+        camera: CameraDescription {
+            look_from: Point3::new(13.0, 2.0, 3.0),
+            look_at: Point3::new(0.0, 0.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 20.0,
+            aperture: if moving { 0.0 } else { 0.1 },
+            focus_dist: Some(10.0),
+            shutter: if moving { (0.0, 1.0) } else { (0.0, 0.0) },
+        },
+        background: BackgroundDescription::default(),
+        materials,
+        objects,
+        lights: vec![],
+        base_directory: None,
+    }
+}
+
+/// The Cornell box lit only by the ceiling light, against a black background.
+fn cornell_box() -> SceneDescription {
+    let mut materials = BTreeMap::new();
+    materials.insert(
+        String::from("red"),
+        lambertian(Color::new(0.65, 0.05, 0.05)),
+    );
+    materials.insert(
+        String::from("white"),
+        lambertian(Color::new(0.73, 0.73, 0.73)),
+    );
+    materials.insert(
+        String::from("green"),
+        lambertian(Color::new(0.12, 0.45, 0.15)),
+    );
+    materials.insert(
+        String::from("light"),
+        MaterialDescription::DiffuseLight {
+            emit: Color::new(15.0, 15.0, 15.0),
+        },
+    );
+
+    let x = Vec3::new(555.0, 0.0, 0.0);
+    let y = Vec3::new(0.0, 555.0, 0.0);
+    let z = Vec3::new(0.0, 0.0, 555.0);
+    let origin = Point3::new(0.0, 0.0, 0.0);
+
+    // Every wall faces into the box.
+    let objects = vec![
+        quad(x, z, y, "green"),
+        quad(origin, y, z, "red"),
+        quad(origin, z, x, "white"),
+        quad(y, x, z, "white"),
+        quad(z, y, x, "white"),
+        quad(
+            Point3::new(343.0, 554.0, 332.0),
+            Vec3::new(-130.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -105.0),
+            "light",
+        ),
+        ObjectDescription::Cuboid {
+            min: Point3::new(130.0, 0.0, 65.0),
+            max: Point3::new(295.0, 165.0, 230.0),
+            material: String::from("white"),
+        },
+        ObjectDescription::Cuboid {
+            min: Point3::new(265.0, 0.0, 295.0),
+            max: Point3::new(430.0, 330.0, 460.0),
+            material: String::from("white"),
+        },
+    ];
+
+    SceneDescription {
+        image: ImageDescription {
+            samples_per_pixel: 200,
+            max_depth: 50,
+            ..image(600, 600)
+        },
+        camera: CameraDescription {
+            look_from: Point3::new(278.0, 278.0, -800.0),
+            look_at: Point3::new(278.0, 278.0, 0.0),
+            vup: Vec3::new(0.0, 1.0, 0.0),
+            vfov: 40.0,
+            aperture: 0.0,
+            focus_dist: None,
+            shutter: (0.0, 0.0),
+        },
+        background: BackgroundDescription::Solid(Color::new(0.0, 0.0, 0.0)),
+        materials,
+        objects,
+        lights: vec![],
+        base_directory: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::presets::{preset, PRESETS};
+    use crate::scene_file::SceneDescription;
+
+    #[test]
+    fn presets_build() {
+        for name in PRESETS {
+            let description = preset(name).unwrap();
+            assert!(!description.objects().unwrap().objects.is_empty());
+
+            // Presets must survive a trip through the scene file format.
+            let reparsed = SceneDescription::parse(&description.to_json()).unwrap();
+            assert_eq!(reparsed.objects, description.objects);
+        }
+
+        assert!(preset("missing").is_none());
+    }
+}
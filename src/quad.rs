@@ -0,0 +1,267 @@
+use crate::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable, HittableList};
+use crate::material::Material;
+use crate::random::random_double;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+/// Parallelogram with corner `q` and edges `u` and `v`. The front face is on the
+/// side `u × v` points to.
+pub struct Quad {
+    pub q: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub material: Material,
+    normal: Vec3,
+    d: f64,
+    w: Vec3,
+}
+
+impl Quad {
+    /// Panics if `u` and `v` are parallel or either is zero.
+    pub fn new(q: Point3, u: Vec3, v: Vec3, material: Material) -> Quad {
+        let n = u.cross(v);
+        let normal = n.unit_vector();
+        Quad {
+            q,
+            u,
+            v,
+            material,
+            normal,
+            d: normal.dot(q),
+            w: n / n.dot(n),
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        self.u.cross(self.v).length()
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(r.direction);
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(r.origin)) / denominator;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        // Express the hit point in the (u, v) frame to test it against the edges.
+        let p = r.at(t);
+        let planar = p - self.q;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let mut record = HitRecord::new_empty();
+        record.t = t;
+        record.p = p;
+        record.local = p;
+        record.u = alpha;
+        record.v = beta;
+        record.tangent = self.u;
+        record.bitangent = self.v;
+        record.set_face_normal(r, self.normal);
+        record.material = &self.material;
+
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let corners = [self.q + self.u, self.q + self.v, self.q + self.u + self.v];
+        Some(
+            corners
+                .iter()
+                .fold(Aabb::new(self.q, self.q), |aabb, &corner| {
+                    Aabb::surrounding_box(aabb, Aabb::new(corner, corner))
+                }),
+        )
+    }
+
+    fn is_light(&self) -> bool {
+        matches!(self.material, Material::DiffuseLight(_))
+    }
+
+    /// Uniform over the quad's area, converted to solid angle.
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let record = match self.hit(&Ray::new(origin, direction), 0.001, f64::INFINITY) {
+            Some(record) => record,
+            None => return 0.0,
+        };
+
+        let distance_squared = record.t * record.t * direction.length_squared();
+        let cosine = (direction.dot(self.normal) / direction.length()).abs();
+        distance_squared / (cosine * self.area())
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        self.q + random_double() * self.u + random_double() * self.v - origin
+    }
+}
+
+/// Axis-aligned box spanning the opposite corners `a` and `b`, made of six outward
+/// facing quads.
+pub fn cuboid(a: Point3, b: Point3, material: Material) -> HittableList {
+    let min = Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+    let max = Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+    let dx = Vec3::new(max.x - min.x, 0.0, 0.0);
+    let dy = Vec3::new(0.0, max.y - min.y, 0.0);
+    let dz = Vec3::new(0.0, 0.0, max.z - min.z);
+
+    let mut sides = HittableList::new();
+    sides.add(Quad::new(
+        Point3::new(min.x, min.y, max.z),
+        dx,
+        dy,
+        material.clone(),
+    )); // front
+    sides.add(Quad::new(
+        Point3::new(max.x, min.y, max.z),
+        -dz,
+        dy,
+        material.clone(),
+    )); // right
+    sides.add(Quad::new(
+        Point3::new(max.x, min.y, min.z),
+        -dx,
+        dy,
+        material.clone(),
+    )); // back
+    sides.add(Quad::new(
+        Point3::new(min.x, min.y, min.z),
+        dz,
+        dy,
+        material.clone(),
+    )); // left
+    sides.add(Quad::new(
+        Point3::new(min.x, max.y, max.z),
+        dx,
+        -dz,
+        material.clone(),
+    )); // top
+    sides.add(Quad::new(
+        Point3::new(min.x, min.y, min.z),
+        dx,
+        dz,
+        material,
+    )); // bottom
+    sides
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::Hittable;
+    use crate::material::{DiffuseLight, Material};
+    use crate::quad::{cuboid, Quad};
+    use crate::random;
+    use crate::ray::Ray;
+    use crate::vec3::{Color, Point3, Vec3};
+
+    fn unit_quad() -> Quad {
+        Quad::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Material::None,
+        )
+    }
+
+    #[test]
+    fn hit_quad() {
+        let quad = unit_quad();
+        let ray = Ray::new(Point3::new(1.5, 0.5, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = quad.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        assert_eq!(record.t, 3.0);
+        assert_eq!(record.p, Point3::new(1.5, 0.5, 0.0));
+        assert_eq!(record.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert!(record.front_face);
+        assert_eq!((record.u, record.v), (0.75, 0.5));
+        assert_eq!(record.tangent, Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(record.geometric_normal, record.normal);
+
+        let behind = Ray::new(Point3::new(1.5, 0.5, -3.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(!quad.hit(&behind, 0.001, f64::INFINITY).unwrap().front_face);
+        assert_eq!(quad.area(), 2.0);
+    }
+
+    #[test]
+    fn miss_quad() {
+        let quad = unit_quad();
+        let outside = Ray::new(Point3::new(2.5, 0.5, 3.0), Vec3::new(0.0, 0.0, -1.0));
+        let parallel = Ray::new(Point3::new(0.5, 0.5, 3.0), Vec3::new(1.0, 0.0, 0.0));
+        let too_far = Ray::new(Point3::new(0.5, 0.5, 3.0), Vec3::new(0.0, 0.0, -1.0));
+
+        assert!(quad.hit(&outside, 0.001, f64::INFINITY).is_none());
+        assert!(quad.hit(&parallel, 0.001, f64::INFINITY).is_none());
+        assert!(quad.hit(&too_far, 0.001, 2.0).is_none());
+    }
+
+    #[test]
+    fn cuboid_faces_outward() {
+        let cube = cuboid(
+            Point3::new(1.0, 1.0, 1.0),
+            Point3::new(-1.0, -1.0, -1.0),
+            Material::None,
+        );
+        let aabb = cube.bounding_box().unwrap();
+        assert_eq!(aabb.minimum, Point3::new(-1.0, -1.0, -1.0));
+        assert_eq!(aabb.maximum, Point3::new(1.0, 1.0, 1.0));
+
+        let directions = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        ];
+        for direction in directions {
+            let ray = Ray::new(direction * 5.0 + Vec3::new(0.1, 0.2, 0.3), -direction);
+            let record = cube.hit(&ray, 0.001, f64::INFINITY).unwrap();
+            assert!(record.front_face, "{:?}", direction);
+            assert_eq!(record.normal, direction);
+        }
+    }
+
+    #[test]
+    fn sample_quad_light() {
+        let light = Quad::new(
+            Point3::new(-1.0, 2.0, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 2.0),
+            Material::DiffuseLight(DiffuseLight::new(Color::new(1.0, 1.0, 1.0))),
+        );
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        assert!(light.is_light());
+        assert!(!unit_quad().is_light());
+
+        // Straight up the quad is 2 away and faces the origin head on.
+        assert!((light.pdf_value(origin, Vec3::new(0.0, 1.0, 0.0)) - 1.0).abs() < 1e-12);
+        assert_eq!(light.pdf_value(origin, Vec3::new(1.0, 0.0, 0.0)), 0.0);
+
+        // Sampled directions hit the quad, and the solid angle density integrates to
+        // the quad's solid angle: averaging 1 / pdf over samples estimates it.
+        random::seed(5);
+        let samples = 20_000;
+        let mut solid_angle = 0.0;
+        for _ in 0..samples {
+            let direction = light.random(origin);
+            let pdf = light.pdf_value(origin, direction);
+            assert!(pdf > 0.0);
+            solid_angle += 1.0 / (pdf * samples as f64);
+        }
+
+        // Solid angle of an a x b rectangle seen from d below its center is
+        // 4 asin(ab / sqrt((a² + 4d²)(b² + 4d²))), here with a = b = d = 2.
+        let expected = 4.0 * (0.2f64).asin();
+        assert!((solid_angle - expected).abs() < 0.01 * expected);
+    }
+}
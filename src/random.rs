@@ -0,0 +1,52 @@
+use std::cell::RefCell;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Reseeds the calling thread's generator. The renderer does this at the start of
+/// every tile so the output only depends on the seed, not on which thread ran it.
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Runs `f` with the calling thread's generator.
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/// Uniform random number in `[0, 1)`.
+pub fn random_double() -> f64 {
+    with_rng(|rng| rng.gen())
+}
+
+/// Uniform random number in `[min, max)`.
+pub fn random_range(min: f64, max: f64) -> f64 {
+    min + (max - min) * random_double()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::random::{random_double, random_range, seed};
+
+    #[test]
+    fn seeded_sequence_repeats() {
+        seed(42);
+        let first: Vec<f64> = (0..10).map(|_| random_double()).collect();
+        seed(42);
+        let second: Vec<f64> = (0..10).map(|_| random_double()).collect();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn range() {
+        for _ in 0..100 {
+            let value = random_range(-2.0, 3.0);
+            assert!((-2.0..3.0).contains(&value));
+        }
+    }
+}
@@ -4,11 +4,20 @@ use crate::vec3::Point3;
 pub struct Ray {
     pub origin: Point3,
     pub direction: Point3,
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Point3) -> Ray {
-        Ray { origin, direction }
+        Ray::with_time(origin, direction, 0.0)
+    }
+
+    pub fn with_time(origin: Point3, direction: Point3, time: f64) -> Ray {
+        Ray {
+            origin,
+            direction,
+            time,
+        }
     }
 
     pub fn at(&self, t: f64) -> Point3 {
@@ -18,7 +27,7 @@ impl Ray {
 
 impl PartialEq for Ray {
     fn eq(&self, other: &Self) -> bool {
-        self.origin == other.origin && self.direction == other.direction
+        self.origin == other.origin && self.direction == other.direction && self.time == other.time
     }
 }
 
@@ -39,6 +48,17 @@ mod tests {
         assert_eq!(result.direction.x, direction.x);
         assert_eq!(result.direction.x, direction.x);
         assert_eq!(result.direction.x, direction.x);
+        assert_eq!(result.time, 0.0);
+    }
+
+    #[test]
+    fn test_with_time() {
+        let origin = Vec3::new(1.0, 2.0, 3.0);
+        let direction = Vec3::new(4.0, 5.0, 6.0);
+        let result = Ray::with_time(origin, direction, 0.5);
+
+        assert_eq!(result.time, 0.5);
+        assert_ne!(result, Ray::new(origin, direction));
     }
 
     #[test]
@@ -0,0 +1,846 @@
+use std::fs::File;
+use std::io::{BufWriter, Error, Write};
+use std::path::Path;
+
+use image::codecs::hdr::HdrEncoder;
+use image::{ImageFormat, ImageResult, Rgb, Rgb32FImage, RgbImage};
+use rayon::prelude::*;
+
+use crate::background::Background;
+use crate::camera::Camera;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Scatterable;
+use crate::random;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::tonemap::ToneMapping;
+use crate::vec3::{Color, Point3, Vec3};
+
+const TILE_SIZE: u32 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderSettings {
+    pub image_width: u32,
+    pub image_height: u32,
+    pub samples_per_pixel: usize,
+    pub max_depth: isize,
+    pub seed: u64,
+    /// Number of worker threads, zero uses every available core.
+    pub threads: usize,
+}
+
+impl Default for RenderSettings {
+    fn default() -> RenderSettings {
+        RenderSettings {
+            image_width: 400,
+            image_height: 225,
+            samples_per_pixel: 100,
+            max_depth: 15,
+            seed: 0,
+            threads: 0,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn aspect_ratio(&self) -> f64 {
+        self.image_width as f64 / self.image_height as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// ASCII (P3) PPM.
+    Ppm,
+    /// Binary (P6) PPM, much smaller and faster to write than `Ppm`.
+    PpmBinary,
+    Png,
+    Jpeg,
+    Tga,
+    Bmp,
+    Tiff,
+    /// Linear 32-bit float OpenEXR.
+    Exr,
+    /// Linear Radiance RGBE.
+    Hdr,
+}
+
+impl OutputFormat {
+    /// Looks a format up by name or file extension, ignoring case.
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "ppm" => Some(OutputFormat::Ppm),
+            "p6" | "ppm-binary" => Some(OutputFormat::PpmBinary),
+            "png" => Some(OutputFormat::Png),
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg),
+            "tga" => Some(OutputFormat::Tga),
+            "bmp" => Some(OutputFormat::Bmp),
+            "tif" | "tiff" => Some(OutputFormat::Tiff),
+            "exr" => Some(OutputFormat::Exr),
+            "hdr" => Some(OutputFormat::Hdr),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Option<OutputFormat> {
+        path.as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(OutputFormat::from_name)
+    }
+}
+
+/// Rendered pixels in rows from top to bottom, holding the linear radiance averaged
+/// over all samples.
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Writes the image, tone mapping it first unless the format stores linear floats.
+    pub fn save(
+        &self,
+        path: impl AsRef<Path>,
+        format: OutputFormat,
+        tone_mapping: &ToneMapping,
+    ) -> ImageResult<()> {
+        let format = match format {
+            OutputFormat::Ppm => return Ok(self.write_ppm(path, tone_mapping)?),
+            OutputFormat::PpmBinary => return Ok(self.write_ppm_binary(path, tone_mapping)?),
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Jpeg => ImageFormat::Jpeg,
+            OutputFormat::Tga => ImageFormat::Tga,
+            OutputFormat::Bmp => ImageFormat::Bmp,
+            OutputFormat::Tiff => ImageFormat::Tiff,
+            OutputFormat::Exr => {
+                return self
+                    .to_rgb32f()
+                    .save_with_format(path, ImageFormat::OpenExr)
+            }
+            OutputFormat::Hdr => return self.write_hdr(path),
+        };
+
+        self.to_rgb8(tone_mapping).save_with_format(path, format)
+    }
+
+    /// Converts to 8-bit sRGB colors.
+    pub fn to_rgb8(&self, tone_mapping: &ToneMapping) -> RgbImage {
+        let bytes = self
+            .pixels
+            .iter()
+            .flat_map(|&pixel| tone_mapping.encode(pixel))
+            .collect();
+
+        RgbImage::from_raw(self.width, self.height, bytes).expect("image buffer size mismatch")
+    }
+
+    /// Converts to linear 32-bit float colors without clamping.
+    pub fn to_rgb32f(&self) -> Rgb32FImage {
+        let values = self
+            .pixels
+            .iter()
+            .flat_map(|pixel| [pixel.x as f32, pixel.y as f32, pixel.z as f32])
+            .collect();
+
+        Rgb32FImage::from_raw(self.width, self.height, values).expect("image buffer size mismatch")
+    }
+
+    pub fn write_ppm(
+        &self,
+        path: impl AsRef<Path>,
+        tone_mapping: &ToneMapping,
+    ) -> Result<(), Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        let header = format!("P3\n{} {}\n255\n", self.width, self.height);
+
+        write!(file, "{}", header)?;
+
+        for pixel in self.to_rgb8(tone_mapping).pixels() {
+            let [r, g, b] = pixel.0;
+            writeln!(file, "{} {} {}", r, g, b)?;
+        }
+
+        file.flush()
+    }
+
+    pub fn write_ppm_binary(
+        &self,
+        path: impl AsRef<Path>,
+        tone_mapping: &ToneMapping,
+    ) -> Result<(), Error> {
+        let mut file = BufWriter::new(File::create(path)?);
+
+        write!(file, "P6\n{} {}\n255\n", self.width, self.height)?;
+        file.write_all(self.to_rgb8(tone_mapping).as_raw())?;
+
+        file.flush()
+    }
+
+    pub fn write_hdr(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let pixels: Vec<Rgb<f32>> = self.to_rgb32f().pixels().copied().collect();
+
+        HdrEncoder::new(file).encode(&pixels, self.width as usize, self.height as usize)
+    }
+}
+
+pub struct Renderer {
+    pub settings: RenderSettings,
+}
+
+impl Renderer {
+    pub fn new(settings: RenderSettings) -> Renderer {
+        Renderer { settings }
+    }
+
+    /// Renders the scene on a thread pool sized by `settings.threads`.
+    pub fn render(&self, scene: &Scene, camera: &Camera) -> Image {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.settings.threads)
+            .build()
+            .expect("failed to build render thread pool");
+
+        pool.install(|| self.render_tiles(scene, camera))
+    }
+
+    /// Renders the image in parallel tiles on the current rayon pool. Each tile
+    /// reseeds its thread's generator from the seed and the tile index, so the result
+    /// does not depend on the number of threads or the order tiles are picked up in.
+    fn render_tiles(&self, scene: &Scene, camera: &Camera) -> Image {
+        let RenderSettings {
+            image_width,
+            image_height,
+            samples_per_pixel,
+            max_depth,
+            seed,
+            ..
+        } = self.settings;
+
+        let tiles = tiles(image_width, image_height);
+        let rendered: Vec<Vec<Color>> = tiles
+            .par_iter()
+            .enumerate()
+            .map(|(index, tile)| {
+                random::seed(seed ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+                let mut colors = vec![];
+                for row in tile.y0..tile.y1 {
+                    let j = image_height - 1 - row;
+                    for i in tile.x0..tile.x1 {
+                        let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+                        for _s in 0..samples_per_pixel {
+                            // Pixel i covers [i, i + 1) of the width, so a single pixel
+                            // still spans the whole viewport.
+                            let u = (i as f64 + random::random_double()) / image_width as f64;
+                            let v = (j as f64 + random::random_double()) / image_height as f64;
+                            let r = camera.get_ray(u, v);
+                            pixel_color += ray_color(&r, scene, max_depth);
+                        }
+                        colors.push(pixel_color / samples_per_pixel as f64);
+                    }
+                }
+                colors
+            })
+            .collect();
+
+        let mut pixels =
+            vec![Color::new(0.0, 0.0, 0.0); image_width as usize * image_height as usize];
+        for (tile, colors) in tiles.iter().zip(rendered) {
+            let tile_width = (tile.x1 - tile.x0) as usize;
+            for (offset, row) in (tile.y0..tile.y1).enumerate() {
+                let start = row as usize * image_width as usize + tile.x0 as usize;
+                pixels[start..start + tile_width]
+                    .copy_from_slice(&colors[offset * tile_width..(offset + 1) * tile_width]);
+            }
+        }
+
+        Image {
+            width: image_width,
+            height: image_height,
+            pixels,
+        }
+    }
+}
+
+struct Tile {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+}
+
+/// Splits the image into tiles, with `y` counted in rows from the top.
+fn tiles(image_width: u32, image_height: u32) -> Vec<Tile> {
+    let mut tiles = vec![];
+    for y0 in (0..image_height).step_by(TILE_SIZE as usize) {
+        for x0 in (0..image_width).step_by(TILE_SIZE as usize) {
+            tiles.push(Tile {
+                x0,
+                y0,
+                x1: (x0 + TILE_SIZE).min(image_width),
+                y1: (y0 + TILE_SIZE).min(image_height),
+            });
+        }
+    }
+    tiles
+}
+
+/// Radiance arriving along `r`, following at most `depth` bounces.
+///
+/// Non-specular hits sample the scene's lights and environment map directly with
+/// shadow rays and also follow one BSDF-sampled bounce. Emission reached either way
+/// is weighted with the power heuristic, so each source counts once on average while
+/// keeping whichever strategy finds it more easily.
+pub fn ray_color(r: &Ray, scene: &Scene, depth: isize) -> Color {
+    trace(r, scene, depth, None)
+}
+
+/// `bsdf_pdf` is the density with which the previous, non-specular bounce picked
+/// `r`. Light found along `r` is then weighted against the chance that light
+/// sampling found it too.
+fn trace(r: &Ray, scene: &Scene, depth: isize, bsdf_pdf: Option<f64>) -> Color {
+    if depth <= 0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let weight = |direction: Vec3| match bsdf_pdf {
+        Some(pdf) => mis_weight(pdf, strategy_pdfs(scene, r.origin, direction, pdf)),
+        None => 1.0,
+    };
+
+    let hit = scene.world.hit(r, 0.001, f64::INFINITY);
+    match hit {
+        Some(mut record) => {
+            let material = record.material;
+            material.apply_bump(&mut record);
+
+            let mut emitted = record.material.emitted(&record);
+            if emitted != Color::new(0.0, 0.0, 0.0) {
+                emitted *= weight(r.direction);
+            }
+
+            if record.material.is_specular() {
+                return match record.material.scatter(r, &record) {
+                    Some((attenuation, scattered)) => {
+                        emitted + attenuation * trace(&scattered, scene, depth - 1, None)
+                    }
+                    None => emitted,
+                };
+            }
+
+            // Light sampling does not depend on the BSDF sample, so a failed scatter
+            // only loses the BSDF half of the estimate.
+            let direct = sample_lights(r, &record, scene);
+            let indirect = match record.material.scatter(r, &record) {
+                Some((attenuation, scattered)) => {
+                    let pdf = record.material.pdf(r, &record, scattered.direction);
+                    attenuation * trace(&scattered, scene, depth - 1, Some(pdf))
+                }
+                None => Color::new(0.0, 0.0, 0.0),
+            };
+            emitted + direct + indirect
+        }
+        None => scene.background.color(r.direction) * weight(r.direction),
+    }
+}
+
+/// Direct light at a non-specular hit: one sample of a randomly picked light and one
+/// of the environment map, each weighted against BSDF sampling with the power
+/// heuristic, plus every punctual light, which BSDF sampling can never find.
+fn sample_lights(r: &Ray, record: &HitRecord, scene: &Scene) -> Color {
+    let mut direct = Color::new(0.0, 0.0, 0.0);
+
+    for light in &scene.punctual_lights {
+        let Some(sample) = light.sample(record.p) else {
+            continue;
+        };
+        let reflectance = record.material.eval(r, record, sample.direction);
+        let shadow_ray = Ray::with_time(record.p, sample.direction, r.time);
+        if reflectance != Color::new(0.0, 0.0, 0.0)
+            && !occluded(scene, &shadow_ray, sample.distance)
+        {
+            direct += reflectance * sample.irradiance;
+        }
+    }
+
+    if !scene.lights.objects.is_empty() {
+        let direction = scene.lights.random(record.p);
+        direct += light_sample(r, record, scene, direction, 1);
+    }
+
+    if let Background::Environment(map) = &scene.background {
+        let sample = map.sample(random::random_double(), random::random_double());
+        if let Some((direction, _, _)) = sample {
+            direct += light_sample(r, record, scene, direction, 2);
+        }
+    }
+
+    direct
+}
+
+/// Reflected radiance from `direction`, picked by the strategy at `strategy` in
+/// `strategy_pdfs`.
+fn light_sample(
+    r: &Ray,
+    record: &HitRecord,
+    scene: &Scene,
+    direction: Vec3,
+    strategy: usize,
+) -> Color {
+    let direction = direction.unit_vector();
+    let bsdf_pdf = record.material.pdf(r, record, direction);
+    let pdfs = strategy_pdfs(scene, record.p, direction, bsdf_pdf);
+    let pdf = pdfs[strategy];
+    if pdf <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let reflectance = record.material.eval(r, record, direction);
+    if reflectance == Color::new(0.0, 0.0, 0.0) {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+
+    let radiance = incoming(scene, &Ray::with_time(record.p, direction, r.time));
+    reflectance * radiance * (mis_weight(pdf, pdfs) / pdf)
+}
+
+/// Densities of BSDF, light list and environment sampling for `direction` seen from
+/// `origin`.
+fn strategy_pdfs(scene: &Scene, origin: Point3, direction: Vec3, bsdf_pdf: f64) -> [f64; 3] {
+    let light_pdf = if scene.lights.objects.is_empty() {
+        0.0
+    } else {
+        scene.lights.pdf_value(origin, direction)
+    };
+    let environment_pdf = match &scene.background {
+        Background::Environment(map) => map.pdf(direction),
+        _ => 0.0,
+    };
+
+    [bsdf_pdf, light_pdf, environment_pdf]
+}
+
+/// Power heuristic weight of a sample drawn with `pdf` among all `pdfs`.
+fn mis_weight(pdf: f64, pdfs: [f64; 3]) -> f64 {
+    let sum: f64 = pdfs.iter().map(|p| p * p).sum();
+    if sum > 0.0 {
+        pdf * pdf / sum
+    } else {
+        0.0
+    }
+}
+
+/// Whether anything blocks `r` before `t`.
+fn occluded(scene: &Scene, r: &Ray, t: f64) -> bool {
+    scene.world.hit(r, 0.001, t * (1.0 - 1e-9)).is_some()
+}
+
+/// Light arriving along `r` without further bounces: emission of the closest hit or
+/// the background.
+fn incoming(scene: &Scene, r: &Ray) -> Color {
+    match scene.world.hit(r, 0.001, f64::INFINITY) {
+        Some(record) => record.material.emitted(&record),
+        None => scene.background.color(r.direction),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use image::codecs::hdr::HdrDecoder;
+
+    use crate::background::{Background, EnvironmentMap};
+    use crate::camera::Camera;
+    use crate::hittable::HittableList;
+    use crate::light::{Falloff, Light};
+    use crate::material::{DiffuseLight, Lambertian, Material, Metal};
+    use crate::quad::Quad;
+    use crate::random;
+    use crate::ray::Ray;
+    use crate::render::{ray_color, tiles, Image, OutputFormat, RenderSettings, Renderer};
+    use crate::scene::Scene;
+    use crate::sphere::Sphere;
+    use crate::tonemap::{Operator, ToneMapping};
+    use crate::vec3::{Color, Point3, Vec3};
+
+    #[test]
+    fn output_format() {
+        assert_eq!(OutputFormat::from_name("PPM"), Some(OutputFormat::Ppm));
+        assert_eq!(
+            OutputFormat::from_path("renders/out.ppm"),
+            Some(OutputFormat::Ppm)
+        );
+        assert_eq!(
+            OutputFormat::from_path("out.JPEG"),
+            Some(OutputFormat::Jpeg)
+        );
+        assert_eq!(OutputFormat::from_name("p6"), Some(OutputFormat::PpmBinary));
+        assert_eq!(OutputFormat::from_path("out"), None);
+        assert_eq!(OutputFormat::from_name("gif"), None);
+    }
+
+    fn test_image() -> Image {
+        Image {
+            width: 3,
+            height: 2,
+            pixels: (0..6)
+                .map(|i| Color::new(i as f64 * 0.25, 1.0, 0.0))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn save_formats() {
+        let image = test_image();
+        let tone_mapping = ToneMapping::default();
+        let directory =
+            std::env::temp_dir().join(format!("raytracing_{}_save_formats", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let path = directory.join("image.ppm");
+        image
+            .save(&path, OutputFormat::PpmBinary, &tone_mapping)
+            .unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..11], b"P6\n3 2\n255\n");
+        assert_eq!(&bytes[11..14], &[0, 255, 0]);
+        assert_eq!(bytes.len(), 11 + 3 * 6);
+        std::fs::remove_file(&path).unwrap();
+
+        for format in [OutputFormat::Png, OutputFormat::Tga, OutputFormat::Bmp] {
+            let path = directory.join(format!("image.{:?}", format));
+            image.save(&path, format, &tone_mapping).unwrap();
+            let loaded = image::open(&path).unwrap().to_rgb8();
+            assert_eq!(loaded, image.to_rgb8(&tone_mapping));
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        // Float formats keep radiance above 1.0.
+        let path = directory.join("image.exr");
+        image.save(&path, OutputFormat::Exr, &tone_mapping).unwrap();
+        let loaded = image::open(&path).unwrap().to_rgb32f();
+        assert_eq!(loaded.dimensions(), (3, 2));
+        assert_eq!(loaded.get_pixel(2, 1).0, [1.25, 1.0, 0.0]);
+
+        // The image crate's generic HDR reader quantizes to 8 bits, so decode directly.
+        let path = directory.join("image.hdr");
+        image.save(&path, OutputFormat::Hdr, &tone_mapping).unwrap();
+        let file = BufReader::new(File::open(&path).unwrap());
+        let loaded = HdrDecoder::new(file).unwrap().read_image_hdr().unwrap();
+        assert_eq!(loaded.len(), 6);
+        assert!((loaded[5].0[0] - 1.25).abs() < 0.01);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn ldr_conversion() {
+        let rgb = test_image().to_rgb8(&ToneMapping::default());
+
+        assert_eq!(rgb.get_pixel(0, 0).0, [0, 255, 0]);
+        assert_eq!(rgb.get_pixel(1, 0).0, [137, 255, 0]);
+        assert_eq!(rgb.get_pixel(2, 1).0, [255, 255, 0]);
+
+        // Reinhard maps 1.0 to half brightness instead of clipping.
+        let rgb = test_image().to_rgb8(&ToneMapping::new(0.0, Operator::Reinhard));
+        assert_eq!(rgb.get_pixel(0, 0).0, [0, 188, 0]);
+    }
+
+    #[test]
+    fn tiles_cover_image() {
+        let tiles = tiles(70, 40);
+        let covered: u32 = tiles
+            .iter()
+            .map(|tile| (tile.x1 - tile.x0) * (tile.y1 - tile.y0))
+            .sum();
+
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(covered, 70 * 40);
+    }
+
+    fn test_scene() -> (Scene, Camera) {
+        let mut world = HittableList::new();
+        let material = Material::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        world.add(Sphere::new(
+            Point3::new(0.0, 0.0, -1.0),
+            0.5,
+            material.clone(),
+        ));
+        world.add(Sphere::new(Point3::new(0.0, -100.5, -1.0), 100.0, material));
+        let camera = Camera::look_at(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            70.0 / 40.0,
+        );
+
+        (Scene::new(world), camera)
+    }
+
+    fn test_settings(threads: usize, seed: u64) -> RenderSettings {
+        RenderSettings {
+            image_width: 70,
+            image_height: 40,
+            samples_per_pixel: 4,
+            max_depth: 5,
+            seed,
+            threads,
+        }
+    }
+
+    #[test]
+    fn render_image() {
+        let (scene, camera) = test_scene();
+        let image = Renderer::new(test_settings(1, 0)).render(&scene, &camera);
+
+        assert_eq!(image.width, 70);
+        assert_eq!(image.height, 40);
+        assert_eq!(image.pixels.len(), 70 * 40);
+
+        // The top row only sees sky, which is brighter than the shadow under the sphere.
+        let sky = image.pixel(35, 0);
+        let shadow = image.pixel(35, 30);
+        assert!(sky.z > shadow.z);
+    }
+
+    #[test]
+    fn render_single_column() {
+        let (scene, camera) = test_scene();
+        let settings = RenderSettings {
+            image_width: 1,
+            image_height: 8,
+            ..test_settings(1, 0)
+        };
+        let image = Renderer::new(settings).render(&scene, &camera);
+
+        assert_eq!(image.pixels.len(), 8);
+        assert!(image.pixels.iter().all(|pixel| pixel.x.is_finite()));
+    }
+
+    #[test]
+    fn render_is_deterministic_across_thread_counts() {
+        let (scene, camera) = test_scene();
+
+        let single = Renderer::new(test_settings(1, 1234)).render(&scene, &camera);
+        let multi = Renderer::new(test_settings(3, 1234)).render(&scene, &camera);
+        let other_seed = Renderer::new(test_settings(3, 4321)).render(&scene, &camera);
+
+        assert!(single.pixels == multi.pixels);
+        assert!(single.pixels != other_seed.pixels);
+    }
+
+    #[test]
+    fn environment_furnace() {
+        // A grey convex object under a uniform white sky reflects exactly its albedo,
+        // so the light sample and the escaping bounce must not both be counted.
+        let mut world = HittableList::new();
+        let material = Material::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        world.add(Sphere::new(Point3::new(0.0, 0.0, -3.0), 1.0, material));
+        let mut scene = Scene::new(world);
+        let sky = vec![Color::new(1.0, 1.0, 1.0); 8 * 4];
+        scene.background = Background::Environment(EnvironmentMap::new(8, 4, sky));
+        let camera = Camera::look_at(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            20.0,
+            1.0,
+        );
+        let settings = RenderSettings {
+            image_width: 8,
+            image_height: 8,
+            samples_per_pixel: 64,
+            max_depth: 5,
+            seed: 0,
+            threads: 1,
+        };
+
+        let image = Renderer::new(settings).render(&scene, &camera);
+        let average = image.pixels.iter().map(|pixel| pixel.x).sum::<f64>() / 64.0;
+
+        assert!((average - 0.5).abs() < 0.02, "average {}", average);
+    }
+
+    #[test]
+    fn sphere_light_over_floor() {
+        // A floor point straight below a sphere light of radiance L, radius r and
+        // distance d receives irradiance π L (r / d)², so a grey floor reflects
+        // albedo L (r / d)².
+        let mut world = HittableList::new();
+        let floor = Material::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let light = Material::DiffuseLight(DiffuseLight::new(Color::new(10.0, 10.0, 10.0)));
+        world.add(Quad::new(
+            Point3::new(-50.0, 0.0, 50.0),
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -100.0),
+            floor,
+        ));
+        world.add(Sphere::new(Point3::new(0.0, 2.0, 0.0), 0.5, light));
+        let mut scene = Scene::new(world);
+        scene.background = Background::Solid(Color::new(0.0, 0.0, 0.0));
+        assert_eq!(scene.lights.objects.len(), 1);
+
+        let ray = Ray::new(Point3::new(0.0, 1.0, 5.0), Vec3::new(0.0, -1.0, -5.0));
+        random::seed(9);
+        let samples = 2000;
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            sum += ray_color(&ray, &scene, 10);
+        }
+        let average = sum / samples as f64;
+
+        assert!(
+            (average.x - 0.5 * 10.0 * 0.0625).abs() < 0.01,
+            "{}",
+            average
+        );
+    }
+
+    /// Glossy floor with the given `fuzz` under a square light with side `size`,
+    /// centered at (0, 2, -4).
+    fn glossy_scene(size: f64, fuzz: f64) -> Scene {
+        let mut world = HittableList::new();
+        let floor = Material::Metal(Metal::new(Color::new(0.8, 0.8, 0.8), fuzz));
+        let light =
+            Material::DiffuseLight(DiffuseLight::new(Color::new(1.0, 1.0, 1.0) / (size * size)));
+        world.add(Quad::new(
+            Point3::new(-50.0, 0.0, 50.0),
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -100.0),
+            floor,
+        ));
+        world.add(Quad::new(
+            Point3::new(-size / 2.0, 2.0, -4.0 - size / 2.0),
+            Vec3::new(size, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, size),
+            light,
+        ));
+        let mut scene = Scene::new(world);
+        scene.background = Background::Solid(Color::new(0.0, 0.0, 0.0));
+        scene
+    }
+
+    fn average_color(scene: &Scene, ray: &Ray, samples: usize) -> (f64, f64) {
+        random::seed(5);
+        let values: Vec<f64> = (0..samples).map(|_| ray_color(ray, scene, 5).x).collect();
+        let mean = values.iter().sum::<f64>() / samples as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / samples as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn glossy_metal_converges_with_mis() {
+        let ray = Ray::new(Point3::new(0.0, 1.0, 2.0), Vec3::new(0.0, -1.0, -2.0));
+        let lobe = Metal::new(Color::new(1.0, 1.0, 1.0), 0.3).exponent();
+
+        // A light small enough for the lobe to be flat across it reflects albedo
+        // times the lobe peak times its power per unit solid angle.
+        let scene = glossy_scene(0.05, 0.3);
+        let distance_squared: f64 = 20.0;
+        let solid_angle = 2.0 / distance_squared.sqrt() / distance_squared;
+        let expected = 0.8 * (lobe + 1.0) / (2.0 * PI) * solid_angle;
+        let (mis, mis_variance) = average_color(&scene, &ray, 2000);
+        assert!(
+            (mis - expected).abs() < 0.02 * expected,
+            "{} {}",
+            mis,
+            expected
+        );
+
+        // Light sampling finds it every time, so single samples are already close.
+        assert!(mis_variance.sqrt() < 0.2 * expected, "{}", mis_variance);
+
+        // A large light is easy for BSDF sampling, and both strategies agree.
+        let scene = glossy_scene(4.0, 0.3);
+        let (mis, _) = average_color(&scene, &ray, 4000);
+        let mut unsampled = glossy_scene(4.0, 0.3);
+        unsampled.lights = HittableList::new();
+        let (bsdf, _) = average_color(&unsampled, &ray, 4000);
+        assert!((mis - bsdf).abs() < 0.03 * bsdf, "{} {}", mis, bsdf);
+    }
+
+    #[test]
+    fn grazing_glossy_metal_keeps_light_samples() {
+        // Seen at a grazing angle, about half of a wide lobe dips below the floor and
+        // scattering fails there. Light sampling must not depend on that.
+        let ray = Ray::new(Point3::new(0.0, 0.1, 20.0), Vec3::new(0.0, -0.1, -20.0));
+        let scene = glossy_scene(0.05, 0.9);
+        let lobe = Metal::new(Color::new(1.0, 1.0, 1.0), 0.9).exponent();
+
+        let reflected = Vec3::new(0.0, 0.1, -20.0).unit_vector();
+        let towards_light = Vec3::new(0.0, 2.0, -4.0).unit_vector();
+        let distance_squared: f64 = 20.0;
+        let solid_angle = 2.0 / distance_squared.sqrt() / distance_squared;
+        let expected =
+            0.8 * (lobe + 1.0) / (2.0 * PI) * reflected.dot(towards_light).powf(lobe) * solid_angle;
+        let (average, _) = average_color(&scene, &ray, 2000);
+
+        assert!(
+            (average - expected).abs() < 0.03 * expected,
+            "{} {}",
+            average,
+            expected
+        );
+    }
+
+    #[test]
+    fn punctual_lights() {
+        // A grey floor 2 below a point light of intensity I reflects
+        // albedo / π · I / 2².
+        let mut world = HittableList::new();
+        let floor = Material::Lambertian(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        world.add(Quad::new(
+            Point3::new(-50.0, 0.0, 50.0),
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -100.0),
+            floor.clone(),
+        ));
+        let mut scene = Scene::new(world);
+        scene.background = Background::Solid(Color::new(0.0, 0.0, 0.0));
+        scene.punctual_lights.push(Light::Point {
+            position: Point3::new(0.0, 2.0, 0.0),
+            intensity: Color::new(8.0, 8.0, 8.0),
+            falloff: Falloff::InverseSquare,
+        });
+
+        let ray = Ray::new(Point3::new(0.0, 1.0, 5.0), Vec3::new(0.0, -1.0, -5.0));
+        let color = ray_color(&ray, &scene, 1);
+        assert!((color.x - 0.5 / PI * 2.0).abs() < 1e-9, "{}", color);
+
+        // A sun shining straight down adds albedo / π · E.
+        scene.punctual_lights.push(Light::Directional {
+            direction: Vec3::new(0.0, -1.0, 0.0),
+            irradiance: Color::new(1.0, 1.0, 1.0),
+            angular_diameter: 0.0,
+        });
+        let color = ray_color(&ray, &scene, 1);
+        assert!((color.x - 0.5 / PI * 3.0).abs() < 1e-9, "{}", color);
+
+        // A ball above the floor shadows both.
+        let mut world = HittableList::new();
+        world.add(Quad::new(
+            Point3::new(-50.0, 0.0, 50.0),
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, -100.0),
+            floor.clone(),
+        ));
+        world.add(Sphere::new(Point3::new(0.0, 1.0, 0.0), 0.5, floor));
+        let lights = scene.punctual_lights.clone();
+        let mut scene = Scene::new(world);
+        scene.background = Background::Solid(Color::new(0.0, 0.0, 0.0));
+        scene.punctual_lights = lights;
+        let ray = Ray::new(Point3::new(0.0, 0.2, 5.0), Vec3::new(0.0, -0.2, -5.0));
+        assert_eq!(ray_color(&ray, &scene, 1), Color::new(0.0, 0.0, 0.0));
+    }
+}
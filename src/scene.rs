@@ -0,0 +1,32 @@
+use crate::background::Background;
+use crate::bvh::Bvh;
+use crate::hittable::HittableList;
+use crate::light::Light;
+
+/// Everything the renderer needs to know about the world, with the objects already
+/// packed into a BVH.
+pub struct Scene {
+    pub world: Bvh,
+    pub background: Background,
+    /// Emitters sampled with shadow rays at every diffuse hit, collected from the
+    /// top-level objects whose `is_light` is true.
+    pub lights: HittableList,
+    /// Point, spot and directional lights, all sampled at every diffuse hit.
+    pub punctual_lights: Vec<Light>,
+}
+
+impl Scene {
+    pub fn new(objects: HittableList) -> Scene {
+        let mut lights = HittableList::new();
+        for object in objects.objects.iter().filter(|object| object.is_light()) {
+            lights.add_shared(object.clone());
+        }
+
+        Scene {
+            world: Bvh::new(objects.objects),
+            background: Background::default(),
+            lights,
+            punctual_lights: vec![],
+        }
+    }
+}
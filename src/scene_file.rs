@@ -0,0 +1,1958 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fmt, fs, io};
+
+use serde_json::{json, Map, Value};
+
+use crate::background::{Background, EnvironmentMap};
+use crate::bump::Bump;
+use crate::camera::Camera;
+use crate::gltf::load_gltf;
+use crate::hittable::HittableList;
+use crate::light::{Falloff, Light};
+use crate::material::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::moving_sphere::MovingSphere;
+use crate::obj::load_obj;
+use crate::ply::load_ply;
+use crate::quad::{cuboid, Quad};
+use crate::render::RenderSettings;
+use crate::scene::Scene;
+use crate::sphere::Sphere;
+use crate::texture::{
+    Checker, ImageTexture, NoiseTexture, Pattern, SolidColor, Space, Texture, VertexColor, WrapMode,
+};
+use crate::triangle::Triangle;
+use crate::vec3::{Color, Point3, Vec3};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io { path: PathBuf, error: io::Error },
+    Json(serde_json::Error),
+    Invalid { key: String, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io { path, error } => write!(f, "{}: {}", path.display(), error),
+            SceneError::Json(error) => write!(f, "invalid JSON: {}", error),
+            SceneError::Invalid { key, message } => write!(f, "{}: {}", key, message),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageDescription {
+    pub width: u32,
+    pub height: u32,
+    pub samples_per_pixel: usize,
+    pub max_depth: isize,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraDescription {
+    pub look_from: Point3,
+    pub look_at: Point3,
+    pub vup: Vec3,
+    pub vfov: f64,
+    pub aperture: f64,
+    /// Defaults to the distance between `look_from` and `look_at`.
+    pub focus_dist: Option<f64>,
+    pub shutter: (f64, f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackgroundDescription {
+    Solid(Color),
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    /// An equirectangular image, see `EnvironmentMap::load` for the formats.
+    Environment {
+        path: PathBuf,
+        rotation: f64,
+        intensity: f64,
+    },
+}
+
+impl Default for BackgroundDescription {
+    fn default() -> BackgroundDescription {
+        BackgroundDescription::Gradient {
+            bottom: Color::new(1.0, 1.0, 1.0),
+            top: Color::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+/// A texture as written in a scene file. Plain colors are stored as arrays.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureDescription {
+    Solid(Color),
+    Checker {
+        scale: f64,
+        odd: Box<TextureDescription>,
+        even: Box<TextureDescription>,
+    },
+    /// An image file, see `load_linear` for the formats.
+    Image {
+        path: PathBuf,
+        wrap: WrapMode,
+    },
+    /// A procedural pattern, see `NoiseTexture`.
+    Noise {
+        pattern: Pattern,
+        seed: u64,
+        scale: f64,
+        octaves: usize,
+        low: Color,
+        high: Color,
+        space: Space,
+    },
+    /// Mesh vertex colors, see `VertexColor`.
+    VertexColor {
+        fallback: Box<TextureDescription>,
+    },
+}
+
+impl From<Color> for TextureDescription {
+    fn from(color: Color) -> TextureDescription {
+        TextureDescription::Solid(color)
+    }
+}
+
+/// A normal or height map as written in a scene file, see `Bump`. Images in it are
+/// loaded as data rather than sRGB colors.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BumpDescription {
+    Normal {
+        map: TextureDescription,
+        strength: f64,
+    },
+    Height {
+        map: TextureDescription,
+        scale: f64,
+    },
+}
+
+/// A material whose textures have not been loaded yet.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaterialDescription {
+    None,
+    Lambertian {
+        albedo: TextureDescription,
+        bump: Option<BumpDescription>,
+    },
+    Metal {
+        albedo: TextureDescription,
+        fuzz: f64,
+        bump: Option<BumpDescription>,
+    },
+    Dielectric {
+        index_of_refraction: f64,
+        bump: Option<BumpDescription>,
+    },
+    DiffuseLight {
+        emit: Color,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectDescription {
+    Sphere {
+        center: Point3,
+        radius: f64,
+        material: String,
+    },
+    MovingSphere {
+        center0: Point3,
+        center1: Point3,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: String,
+    },
+    Triangle {
+        v0: Point3,
+        v1: Point3,
+        v2: Point3,
+        material: String,
+    },
+    /// Parallelogram with corner `q` and edges `u` and `v`, facing along `u × v`.
+    Quad {
+        q: Point3,
+        u: Vec3,
+        v: Vec3,
+        material: String,
+    },
+    /// Axis-aligned box between two opposite corners.
+    Cuboid {
+        min: Point3,
+        max: Point3,
+        material: String,
+    },
+    /// An OBJ, PLY, glTF or GLB file, chosen by extension. OBJ and glTF files bring
+    /// their own materials, so `material` is only allowed for PLY files.
+    Mesh {
+        path: PathBuf,
+        material: Option<String>,
+    },
+}
+
+/// A scene as stored in a JSON scene file: image settings, a camera, named
+/// materials, objects referring to those materials and lights without geometry.
+///
+/// ```json
+/// {
+///   "image": { "width": 400, "height": 225, "samples_per_pixel": 100, "max_depth": 15 },
+///   "camera": { "look_from": [0, 0, 0], "look_at": [0, 0, -1], "vfov": 90 },
+///   "background": { "type": "gradient", "bottom": [1, 1, 1], "top": [0.5, 0.7, 1] },
+///   "materials": {
+///     "ground": { "type": "lambertian", "albedo": [0.8, 0.8, 0.0] },
+///     "tiles": { "type": "lambertian", "albedo": {
+///       "type": "checker", "scale": 0.5, "odd": [0, 0, 0],
+///       "even": { "type": "image", "path": "marble.png", "wrap": "repeat" }
+///     } },
+///     "bricks": { "type": "lambertian", "albedo": [0.6, 0.3, 0.2], "bump": {
+///       "type": "normal", "map": { "type": "image", "path": "bricks_normal.png" }, "strength": 1
+///     } }
+///   },
+///   "objects": [
+///     { "type": "sphere", "center": [0, -100.5, -1], "radius": 100, "material": "ground" }
+///   ],
+///   "lights": [
+///     { "type": "point", "position": [0, 2, 0], "intensity": [4, 4, 4] },
+///     { "type": "spot", "position": [0, 2, 0], "direction": [0, -1, 0], "intensity": [4, 4, 4],
+///       "inner_angle": 15, "outer_angle": 25, "falloff": "linear" },
+///     { "type": "directional", "direction": [0, -1, -1], "irradiance": [2, 2, 2],
+///       "angular_diameter": 0.5 }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SceneDescription {
+    pub image: ImageDescription,
+    pub camera: CameraDescription,
+    pub background: BackgroundDescription,
+    pub materials: BTreeMap<String, MaterialDescription>,
+    pub objects: Vec<ObjectDescription>,
+    pub lights: Vec<Light>,
+    /// Directory that relative mesh and texture paths are resolved against.
+    pub base_directory: Option<PathBuf>,
+}
+
+impl SceneDescription {
+    pub fn load(path: impl AsRef<Path>) -> Result<SceneDescription, SceneError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|error| SceneError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+
+        let mut description = SceneDescription::parse(&source)?;
+        description.base_directory = path.parent().map(Path::to_path_buf);
+        Ok(description)
+    }
+
+    pub fn parse(source: &str) -> Result<SceneDescription, SceneError> {
+        let root: Value = serde_json::from_str(source).map_err(SceneError::Json)?;
+        let root = object(&root, "scene")?;
+        check_keys(
+            root,
+            &[
+                "image",
+                "camera",
+                "background",
+                "materials",
+                "objects",
+                "lights",
+            ],
+            "",
+        )?;
+
+        let image = parse_image(required(root, "image", "")?, "image")?;
+        let camera = parse_camera(required(root, "camera", "")?, "camera")?;
+        let background = match root.get("background") {
+            Some(value) => parse_background(value, "background")?,
+            None => BackgroundDescription::default(),
+        };
+
+        let mut materials = BTreeMap::new();
+        if let Some(value) = root.get("materials") {
+            for (name, value) in object(value, "materials")? {
+                let key = format!("materials.{}", name);
+                materials.insert(name.clone(), parse_material(value, &key)?);
+            }
+        }
+
+        let mut objects = vec![];
+        if let Some(value) = root.get("objects") {
+            let items = value
+                .as_array()
+                .ok_or_else(|| invalid("objects", "expected an array"))?;
+            for (index, value) in items.iter().enumerate() {
+                let key = format!("objects[{}]", index);
+                objects.push(parse_object(value, &key, &materials)?);
+            }
+        }
+
+        let mut lights = vec![];
+        if let Some(value) = root.get("lights") {
+            let items = value
+                .as_array()
+                .ok_or_else(|| invalid("lights", "expected an array"))?;
+            for (index, value) in items.iter().enumerate() {
+                lights.push(parse_light(value, &format!("lights[{}]", index))?);
+            }
+        }
+
+        Ok(SceneDescription {
+            image,
+            camera,
+            background,
+            materials,
+            objects,
+            lights,
+            base_directory: None,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let materials: Map<String, Value> = self
+            .materials
+            .iter()
+            .map(|(name, material)| (name.clone(), material_to_json(material)))
+            .collect();
+        let objects: Vec<Value> = self.objects.iter().map(object_to_json).collect();
+        let lights: Vec<Value> = self.lights.iter().map(light_to_json).collect();
+
+        let mut camera = json!({
+            "look_from": vector_to_json(self.camera.look_from),
+            "look_at": vector_to_json(self.camera.look_at),
+            "vup": vector_to_json(self.camera.vup),
+            "vfov": self.camera.vfov,
+            "aperture": self.camera.aperture,
+            "shutter": [self.camera.shutter.0, self.camera.shutter.1],
+        });
+        if let Some(focus_dist) = self.camera.focus_dist {
+            camera["focus_dist"] = json!(focus_dist);
+        }
+
+        let root = json!({
+            "image": {
+                "width": self.image.width,
+                "height": self.image.height,
+                "samples_per_pixel": self.image.samples_per_pixel,
+                "max_depth": self.image.max_depth,
+                "seed": self.image.seed,
+            },
+            "camera": camera,
+            "background": background_to_json(&self.background),
+            "materials": materials,
+            "objects": objects,
+            "lights": lights,
+        });
+
+        serde_json::to_string_pretty(&root).expect("scene values are always serializable")
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), io::Error> {
+        fs::write(path, self.to_json())
+    }
+
+    pub fn settings(&self) -> RenderSettings {
+        RenderSettings {
+            image_width: self.image.width,
+            image_height: self.image.height,
+            samples_per_pixel: self.image.samples_per_pixel,
+            max_depth: self.image.max_depth,
+            seed: self.image.seed,
+            ..RenderSettings::default()
+        }
+    }
+
+    pub fn camera(&self) -> Camera {
+        let description = &self.camera;
+        let aspect_ratio = self.image.width as f64 / self.image.height as f64;
+        let mut camera = Camera::look_at(
+            description.look_from,
+            description.look_at,
+            description.vup,
+            description.vfov,
+            aspect_ratio,
+        );
+
+        let focus_dist = description
+            .focus_dist
+            .unwrap_or_else(|| (description.look_from - description.look_at).length());
+        camera.set_focus(description.aperture, focus_dist);
+        camera.set_shutter(description.shutter.0, description.shutter.1);
+        camera
+    }
+
+    /// Builds the background, loading the environment map if there is one.
+    pub fn background(&self) -> Result<Background, SceneError> {
+        match &self.background {
+            BackgroundDescription::Solid(color) => Ok(Background::Solid(*color)),
+            BackgroundDescription::Gradient { bottom, top } => Ok(Background::Gradient {
+                bottom: *bottom,
+                top: *top,
+            }),
+            BackgroundDescription::Environment {
+                path,
+                rotation,
+                intensity,
+            } => {
+                let mut map = EnvironmentMap::load(self.resolve(path))
+                    .map_err(|error| invalid("background.path", &error.to_string()))?;
+                map.rotation = *rotation;
+                map.intensity = *intensity;
+                Ok(Background::Environment(map))
+            }
+        }
+    }
+
+    /// Builds the objects, background and lights into a renderable scene.
+    pub fn scene(&self) -> Result<Scene, SceneError> {
+        let mut scene = Scene::new(self.objects()?);
+        scene.background = self.background()?;
+        scene.punctual_lights = self.lights.clone();
+        Ok(scene)
+    }
+
+    /// Builds the named materials, loading any image textures they use.
+    pub fn materials(&self) -> Result<BTreeMap<String, Material>, SceneError> {
+        let mut materials = BTreeMap::new();
+        for (name, material) in &self.materials {
+            let key = format!("materials.{}", name);
+            let material = match material {
+                MaterialDescription::None => Material::None,
+                MaterialDescription::Lambertian { albedo, bump } => {
+                    let mut lambertian = Lambertian::textured(self.texture(
+                        albedo,
+                        &child(&key, "albedo"),
+                        false,
+                    )?);
+                    lambertian.bump = self.bump(bump, &child(&key, "bump"))?;
+                    Material::Lambertian(lambertian)
+                }
+                MaterialDescription::Metal { albedo, fuzz, bump } => {
+                    let mut metal = Metal::textured(
+                        self.texture(albedo, &child(&key, "albedo"), false)?,
+                        *fuzz,
+                    );
+                    metal.bump = self.bump(bump, &child(&key, "bump"))?;
+                    Material::Metal(metal)
+                }
+                MaterialDescription::Dielectric {
+                    index_of_refraction,
+                    bump,
+                } => {
+                    let mut dielectric = Dielectric::new(*index_of_refraction);
+                    dielectric.bump = self.bump(bump, &child(&key, "bump"))?;
+                    Material::Dielectric(dielectric)
+                }
+                MaterialDescription::DiffuseLight { emit } => {
+                    Material::DiffuseLight(DiffuseLight::new(*emit))
+                }
+            };
+            materials.insert(name.clone(), material);
+        }
+
+        Ok(materials)
+    }
+
+    fn bump(&self, bump: &Option<BumpDescription>, key: &str) -> Result<Option<Bump>, SceneError> {
+        Ok(match bump {
+            None => None,
+            Some(BumpDescription::Normal { map, strength }) => Some(Bump::Normal {
+                map: self.texture(map, &child(key, "map"), true)?,
+                strength: *strength,
+            }),
+            Some(BumpDescription::Height { map, scale }) => Some(Bump::Height {
+                map: self.texture(map, &child(key, "map"), true)?,
+                scale: *scale,
+            }),
+        })
+    }
+
+    /// Builds a texture. Images of `data` textures, such as normal maps, are not
+    /// sRGB decoded.
+    fn texture(
+        &self,
+        texture: &TextureDescription,
+        key: &str,
+        data: bool,
+    ) -> Result<Arc<dyn Texture>, SceneError> {
+        Ok(match texture {
+            TextureDescription::Solid(color) => Arc::new(SolidColor::new(*color)),
+            TextureDescription::Checker { scale, odd, even } => Arc::new(Checker::new(
+                *scale,
+                self.texture(odd, &child(key, "odd"), data)?,
+                self.texture(even, &child(key, "even"), data)?,
+            )),
+            TextureDescription::Image { path, wrap } => {
+                let path = self.resolve(path);
+                let image = if data {
+                    ImageTexture::load_data(path, *wrap)
+                } else {
+                    ImageTexture::load(path, *wrap)
+                };
+                Arc::new(image.map_err(|error| invalid(&child(key, "path"), &error.to_string()))?)
+            }
+            TextureDescription::Noise {
+                pattern,
+                seed,
+                scale,
+                octaves,
+                low,
+                high,
+                space,
+            } => {
+                let mut texture = NoiseTexture::new(*pattern, *seed);
+                texture.scale = *scale;
+                texture.octaves = *octaves;
+                texture.low = *low;
+                texture.high = *high;
+                texture.space = *space;
+                Arc::new(texture)
+            }
+            TextureDescription::VertexColor { fallback } => Arc::new(VertexColor::new(
+                self.texture(fallback, &child(key, "fallback"), data)?,
+            )),
+        })
+    }
+
+    /// Builds the objects, loading any mesh and texture files they reference.
+    pub fn objects(&self) -> Result<HittableList, SceneError> {
+        let materials = self.materials()?;
+        let mut list = HittableList::new();
+
+        for (index, object) in self.objects.iter().enumerate() {
+            // Descriptions built in code never went through `parse`, so check the name.
+            let lookup = |name: &String| {
+                materials.get(name).cloned().ok_or_else(|| {
+                    invalid(
+                        &format!("objects[{}].material", index),
+                        &format!("unknown material '{}'", name),
+                    )
+                })
+            };
+            match object {
+                ObjectDescription::Sphere {
+                    center,
+                    radius,
+                    material,
+                } => list.add(Sphere::new(*center, *radius, lookup(material)?)),
+                ObjectDescription::MovingSphere {
+                    center0,
+                    center1,
+                    time0,
+                    time1,
+                    radius,
+                    material,
+                } => list.add(MovingSphere::new(
+                    *center0,
+                    *center1,
+                    *time0,
+                    *time1,
+                    *radius,
+                    lookup(material)?,
+                )),
+                ObjectDescription::Triangle {
+                    v0,
+                    v1,
+                    v2,
+                    material,
+                } => list.add(Triangle::new(*v0, *v1, *v2, lookup(material)?)),
+                ObjectDescription::Quad { q, u, v, material } => {
+                    list.add(Quad::new(*q, *u, *v, lookup(material)?))
+                }
+                ObjectDescription::Cuboid { min, max, material } => {
+                    list.add(cuboid(*min, *max, lookup(material)?))
+                }
+                ObjectDescription::Mesh { path, material } => {
+                    if material.is_some() && !mesh_takes_material(path) {
+                        return Err(invalid(
+                            &format!("objects[{}].material", index),
+                            MESH_MATERIAL_ERROR,
+                        ));
+                    }
+                    let key = format!("objects[{}].path", index);
+                    let material = match material {
+                        Some(name) => Some(lookup(name)?),
+                        None => None,
+                    };
+                    self.load_mesh(path, material, &key, &mut list)?;
+                }
+            }
+        }
+
+        Ok(list)
+    }
+
+    fn load_mesh(
+        &self,
+        path: &Path,
+        material: Option<Material>,
+        key: &str,
+        list: &mut HittableList,
+    ) -> Result<(), SceneError> {
+        let resolved = self.resolve(path);
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("obj") => {
+                let groups =
+                    load_obj(&resolved).map_err(|error| invalid(key, &error.to_string()))?;
+                for group in groups {
+                    list.add(group.mesh);
+                }
+            }
+            Some("ply") => {
+                // Without an override, colored scans show their vertex colors.
+                let material = material.unwrap_or_else(|| {
+                    let grey = Arc::new(SolidColor::new(Color::new(0.8, 0.8, 0.8)));
+                    Material::Lambertian(Lambertian::textured(Arc::new(VertexColor::new(grey))))
+                });
+                let mesh = load_ply(&resolved, material)
+                    .map_err(|error| invalid(key, &error.to_string()))?;
+                list.add(mesh);
+            }
+            Some("gltf") | Some("glb") => {
+                let aspect_ratio = self.image.width as f64 / self.image.height as f64;
+                let scene = load_gltf(&resolved, aspect_ratio)
+                    .map_err(|error| invalid(key, &error.to_string()))?;
+                list.objects.extend(scene.objects.objects);
+            }
+            _ => return Err(invalid(key, "unsupported mesh format")),
+        }
+
+        Ok(())
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        match &self.base_directory {
+            Some(directory) => directory.join(path),
+            None => path.to_path_buf(),
+        }
+    }
+}
+
+const MESH_MATERIAL_ERROR: &str =
+    "only PLY meshes take a material, OBJ and glTF files bring their own";
+
+/// Whether a mesh file needs its material from the scene, which is only true for PLY.
+fn mesh_takes_material(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("ply"))
+}
+
+fn parse_image(value: &Value, key: &str) -> Result<ImageDescription, SceneError> {
+    let image = object(value, key)?;
+    check_keys(
+        image,
+        &["width", "height", "samples_per_pixel", "max_depth", "seed"],
+        key,
+    )?;
+
+    let width = unsigned(required(image, "width", key)?, &child(key, "width"))?;
+    let height = unsigned(required(image, "height", key)?, &child(key, "height"))?;
+    if width == 0 || height == 0 {
+        return Err(invalid(key, "width and height must be positive"));
+    }
+
+    let samples_per_pixel = match image.get("samples_per_pixel") {
+        Some(value) => unsigned(value, &child(key, "samples_per_pixel"))? as usize,
+        None => 100,
+    };
+    if samples_per_pixel == 0 {
+        return Err(invalid(
+            &child(key, "samples_per_pixel"),
+            "must be positive",
+        ));
+    }
+    let max_depth = match image.get("max_depth") {
+        Some(value) => unsigned(value, &child(key, "max_depth"))? as isize,
+        None => 15,
+    };
+    let seed = match image.get("seed") {
+        Some(value) => unsigned(value, &child(key, "seed"))?,
+        None => 0,
+    };
+
+    let dimension = |value: u64, name: &str| {
+        u32::try_from(value).map_err(|_| invalid(&child(key, name), "too large"))
+    };
+
+    Ok(ImageDescription {
+        width: dimension(width, "width")?,
+        height: dimension(height, "height")?,
+        samples_per_pixel,
+        max_depth,
+        seed,
+    })
+}
+
+fn parse_camera(value: &Value, key: &str) -> Result<CameraDescription, SceneError> {
+    let camera = object(value, key)?;
+    check_keys(
+        camera,
+        &[
+            "look_from",
+            "look_at",
+            "vup",
+            "vfov",
+            "aperture",
+            "focus_dist",
+            "shutter",
+        ],
+        key,
+    )?;
+
+    let optional_number = |name: &str, default: f64| match camera.get(name) {
+        Some(value) => number(value, &child(key, name)),
+        None => Ok(default),
+    };
+
+    let shutter = match camera.get("shutter") {
+        Some(value) => {
+            let shutter_key = child(key, "shutter");
+            match value.as_array().map(Vec::as_slice) {
+                Some([open, close]) => (number(open, &shutter_key)?, number(close, &shutter_key)?),
+                _ => return Err(invalid(&shutter_key, "expected [open, close]")),
+            }
+        }
+        None => (0.0, 0.0),
+    };
+
+    let look_from = vector(
+        required(camera, "look_from", key)?,
+        &child(key, "look_from"),
+    )?;
+    let look_at = vector(required(camera, "look_at", key)?, &child(key, "look_at"))?;
+    let vup = match camera.get("vup") {
+        Some(value) => vector(value, &child(key, "vup"))?,
+        None => Vec3::new(0.0, 1.0, 0.0),
+    };
+    let view = look_from - look_at;
+    if view.near_zero() {
+        return Err(invalid(
+            &child(key, "look_at"),
+            "must differ from look_from",
+        ));
+    }
+    if vup.cross(view.unit_vector()).near_zero() {
+        return Err(invalid(
+            &child(key, "vup"),
+            "must not be parallel to the view direction",
+        ));
+    }
+
+    let vfov = optional_number("vfov", 90.0)?;
+    if vfov.is_nan() || vfov <= 0.0 || vfov >= 180.0 {
+        return Err(invalid(
+            &child(key, "vfov"),
+            "must be between 0 and 180 degrees",
+        ));
+    }
+    let focus_dist = match camera.get("focus_dist") {
+        Some(value) => {
+            let focus_key = child(key, "focus_dist");
+            let focus_dist = number(value, &focus_key)?;
+            if !focus_dist.is_finite() || focus_dist <= 0.0 {
+                return Err(invalid(&focus_key, "must be positive"));
+            }
+            Some(focus_dist)
+        }
+        None => None,
+    };
+
+    Ok(CameraDescription {
+        look_from,
+        look_at,
+        vup,
+        vfov,
+        aperture: optional_number("aperture", 0.0)?,
+        focus_dist,
+        shutter,
+    })
+}
+
+fn parse_background(value: &Value, key: &str) -> Result<BackgroundDescription, SceneError> {
+    let background = object(value, key)?;
+    let kind = string(required(background, "type", key)?, &child(key, "type"))?;
+    let color = |name: &str| vector(required(background, name, key)?, &child(key, name));
+
+    match kind {
+        "solid" => {
+            check_keys(background, &["type", "color"], key)?;
+            Ok(BackgroundDescription::Solid(color("color")?))
+        }
+        "gradient" => {
+            check_keys(background, &["type", "bottom", "top"], key)?;
+            Ok(BackgroundDescription::Gradient {
+                bottom: color("bottom")?,
+                top: color("top")?,
+            })
+        }
+        "environment" => {
+            check_keys(background, &["type", "path", "rotation", "intensity"], key)?;
+            let optional_number = |name: &str, default: f64| match background.get(name) {
+                Some(value) => number(value, &child(key, name)),
+                None => Ok(default),
+            };
+            let path = string(required(background, "path", key)?, &child(key, "path"))?;
+            Ok(BackgroundDescription::Environment {
+                path: PathBuf::from(path),
+                rotation: optional_number("rotation", 0.0)?,
+                intensity: optional_number("intensity", 1.0)?,
+            })
+        }
+        _ => Err(invalid(
+            &child(key, "type"),
+            &format!("unknown background type '{}'", kind),
+        )),
+    }
+}
+
+fn parse_material(value: &Value, key: &str) -> Result<MaterialDescription, SceneError> {
+    let material = object(value, key)?;
+    let kind = string(required(material, "type", key)?, &child(key, "type"))?;
+    let albedo = || parse_texture(required(material, "albedo", key)?, &child(key, "albedo"));
+    let bump = || match material.get("bump") {
+        Some(value) => parse_bump(value, &child(key, "bump")).map(Some),
+        None => Ok(None),
+    };
+
+    match kind {
+        "none" => {
+            check_keys(material, &["type"], key)?;
+            Ok(MaterialDescription::None)
+        }
+        "lambertian" => {
+            check_keys(material, &["type", "albedo", "bump"], key)?;
+            Ok(MaterialDescription::Lambertian {
+                albedo: albedo()?,
+                bump: bump()?,
+            })
+        }
+        "metal" => {
+            check_keys(material, &["type", "albedo", "fuzz", "bump"], key)?;
+            let fuzz = match material.get("fuzz") {
+                Some(value) => number(value, &child(key, "fuzz"))?,
+                None => 0.0,
+            };
+            Ok(MaterialDescription::Metal {
+                albedo: albedo()?,
+                fuzz,
+                bump: bump()?,
+            })
+        }
+        "dielectric" => {
+            check_keys(material, &["type", "index_of_refraction", "bump"], key)?;
+            let index_of_refraction = number(
+                required(material, "index_of_refraction", key)?,
+                &child(key, "index_of_refraction"),
+            )?;
+            Ok(MaterialDescription::Dielectric {
+                index_of_refraction,
+                bump: bump()?,
+            })
+        }
+        "diffuse_light" => {
+            check_keys(material, &["type", "emit"], key)?;
+            let emit = vector(required(material, "emit", key)?, &child(key, "emit"))?;
+            Ok(MaterialDescription::DiffuseLight { emit })
+        }
+        _ => Err(invalid(
+            &child(key, "type"),
+            &format!("unknown material type '{}'", kind),
+        )),
+    }
+}
+
+fn parse_bump(value: &Value, key: &str) -> Result<BumpDescription, SceneError> {
+    let bump = object(value, key)?;
+    let kind = string(required(bump, "type", key)?, &child(key, "type"))?;
+    let map = || parse_texture(required(bump, "map", key)?, &child(key, "map"));
+
+    match kind {
+        "normal" => {
+            check_keys(bump, &["type", "map", "strength"], key)?;
+            let strength = match bump.get("strength") {
+                Some(value) => number(value, &child(key, "strength"))?,
+                None => 1.0,
+            };
+            Ok(BumpDescription::Normal {
+                map: map()?,
+                strength,
+            })
+        }
+        "height" => {
+            check_keys(bump, &["type", "map", "scale"], key)?;
+            Ok(BumpDescription::Height {
+                map: map()?,
+                scale: number(required(bump, "scale", key)?, &child(key, "scale"))?,
+            })
+        }
+        _ => Err(invalid(
+            &child(key, "type"),
+            &format!("unknown bump type '{}'", kind),
+        )),
+    }
+}
+
+/// A color array or a texture object.
+fn parse_texture(value: &Value, key: &str) -> Result<TextureDescription, SceneError> {
+    if value.is_array() {
+        return Ok(TextureDescription::Solid(vector(value, key)?));
+    }
+
+    let texture = object(value, key)?;
+    let kind = string(required(texture, "type", key)?, &child(key, "type"))?;
+    let nested = |name: &str| parse_texture(required(texture, name, key)?, &child(key, name));
+
+    match kind {
+        "checker" => {
+            check_keys(texture, &["type", "scale", "odd", "even"], key)?;
+            Ok(TextureDescription::Checker {
+                scale: number(required(texture, "scale", key)?, &child(key, "scale"))?,
+                odd: Box::new(nested("odd")?),
+                even: Box::new(nested("even")?),
+            })
+        }
+        "image" => {
+            check_keys(texture, &["type", "path", "wrap"], key)?;
+            let path = string(required(texture, "path", key)?, &child(key, "path"))?;
+            let wrap = match texture.get("wrap") {
+                Some(value) => {
+                    let wrap_key = child(key, "wrap");
+                    let name = string(value, &wrap_key)?;
+                    WrapMode::from_name(name).ok_or_else(|| {
+                        invalid(&wrap_key, &format!("unknown wrap mode '{}'", name))
+                    })?
+                }
+                None => WrapMode::Repeat,
+            };
+            Ok(TextureDescription::Image {
+                path: PathBuf::from(path),
+                wrap,
+            })
+        }
+        "noise" => {
+            check_keys(
+                texture,
+                &[
+                    "type", "pattern", "seed", "scale", "octaves", "low", "high", "space",
+                ],
+                key,
+            )?;
+            let defaults = NoiseTexture::new(Pattern::Noise, 0);
+            let pattern_key = child(key, "pattern");
+            let pattern_name = string(required(texture, "pattern", key)?, &pattern_key)?;
+            let pattern = Pattern::from_name(pattern_name).ok_or_else(|| {
+                invalid(&pattern_key, &format!("unknown pattern '{}'", pattern_name))
+            })?;
+            let space = match texture.get("space") {
+                Some(value) => {
+                    let space_key = child(key, "space");
+                    let name = string(value, &space_key)?;
+                    Space::from_name(name)
+                        .ok_or_else(|| invalid(&space_key, &format!("unknown space '{}'", name)))?
+                }
+                None => defaults.space,
+            };
+            let optional_number = |name: &str, default: f64| match texture.get(name) {
+                Some(value) => number(value, &child(key, name)),
+                None => Ok(default),
+            };
+            let optional_unsigned = |name: &str, default: u64| match texture.get(name) {
+                Some(value) => unsigned(value, &child(key, name)),
+                None => Ok(default),
+            };
+            let optional_color = |name: &str, default: Color| match texture.get(name) {
+                Some(value) => vector(value, &child(key, name)),
+                None => Ok(default),
+            };
+            Ok(TextureDescription::Noise {
+                pattern,
+                seed: optional_unsigned("seed", 0)?,
+                scale: optional_number("scale", defaults.scale)?,
+                octaves: optional_unsigned("octaves", defaults.octaves as u64)? as usize,
+                low: optional_color("low", defaults.low)?,
+                high: optional_color("high", defaults.high)?,
+                space,
+            })
+        }
+        "vertex_color" => {
+            check_keys(texture, &["type", "fallback"], key)?;
+            Ok(TextureDescription::VertexColor {
+                fallback: Box::new(match texture.get("fallback") {
+                    Some(value) => parse_texture(value, &child(key, "fallback"))?,
+                    None => TextureDescription::Solid(Color::new(0.8, 0.8, 0.8)),
+                }),
+            })
+        }
+        _ => Err(invalid(
+            &child(key, "type"),
+            &format!("unknown texture type '{}'", kind),
+        )),
+    }
+}
+
+fn parse_object(
+    value: &Value,
+    key: &str,
+    materials: &BTreeMap<String, MaterialDescription>,
+) -> Result<ObjectDescription, SceneError> {
+    let object = object(value, key)?;
+    let kind = string(required(object, "type", key)?, &child(key, "type"))?;
+
+    let point = |name: &str| vector(required(object, name, key)?, &child(key, name));
+    let scalar = |name: &str| number(required(object, name, key)?, &child(key, name));
+    let material = || {
+        let material_key = child(key, "material");
+        let name = string(required(object, "material", key)?, &material_key)?;
+        if !materials.contains_key(name) {
+            return Err(invalid(
+                &material_key,
+                &format!("unknown material '{}'", name),
+            ));
+        }
+        Ok(name.to_string())
+    };
+
+    match kind {
+        "sphere" => {
+            check_keys(object, &["type", "center", "radius", "material"], key)?;
+            Ok(ObjectDescription::Sphere {
+                center: point("center")?,
+                radius: scalar("radius")?,
+                material: material()?,
+            })
+        }
+        "moving_sphere" => {
+            check_keys(
+                object,
+                &[
+                    "type", "center0", "center1", "time0", "time1", "radius", "material",
+                ],
+                key,
+            )?;
+            Ok(ObjectDescription::MovingSphere {
+                center0: point("center0")?,
+                center1: point("center1")?,
+                time0: scalar("time0")?,
+                time1: scalar("time1")?,
+                radius: scalar("radius")?,
+                material: material()?,
+            })
+        }
+        "triangle" => {
+            check_keys(object, &["type", "v0", "v1", "v2", "material"], key)?;
+            Ok(ObjectDescription::Triangle {
+                v0: point("v0")?,
+                v1: point("v1")?,
+                v2: point("v2")?,
+                material: material()?,
+            })
+        }
+        "quad" => {
+            check_keys(object, &["type", "q", "u", "v", "material"], key)?;
+            let (u, v) = (point("u")?, point("v")?);
+            if u.cross(v).near_zero() {
+                return Err(invalid(
+                    &child(key, "u"),
+                    "u and v must be non-zero and not parallel",
+                ));
+            }
+            Ok(ObjectDescription::Quad {
+                q: point("q")?,
+                u,
+                v,
+                material: material()?,
+            })
+        }
+        "cuboid" => {
+            check_keys(object, &["type", "min", "max", "material"], key)?;
+            let (min, max) = (point("min")?, point("max")?);
+            // Every face needs two non-zero edges, so the box cannot be flat.
+            let extent = max - min;
+            let (dx, dy, dz) = (
+                Vec3::new(extent.x, 0.0, 0.0),
+                Vec3::new(0.0, extent.y, 0.0),
+                Vec3::new(0.0, 0.0, extent.z),
+            );
+            if [dx.cross(dy), dy.cross(dz), dz.cross(dx)]
+                .iter()
+                .any(Vec3::near_zero)
+            {
+                return Err(invalid(
+                    &child(key, "max"),
+                    "must differ from min along every axis",
+                ));
+            }
+            Ok(ObjectDescription::Cuboid {
+                min,
+                max,
+                material: material()?,
+            })
+        }
+        "mesh" => {
+            check_keys(object, &["type", "path", "material"], key)?;
+            let path = string(required(object, "path", key)?, &child(key, "path"))?;
+            let material = match object.get("material") {
+                Some(_) if !mesh_takes_material(Path::new(path)) => {
+                    return Err(invalid(&child(key, "material"), MESH_MATERIAL_ERROR));
+                }
+                Some(_) => Some(material()?),
+                None => None,
+            };
+            Ok(ObjectDescription::Mesh {
+                path: PathBuf::from(path),
+                material,
+            })
+        }
+        _ => Err(invalid(
+            &child(key, "type"),
+            &format!("unknown object type '{}'", kind),
+        )),
+    }
+}
+
+fn parse_light(value: &Value, key: &str) -> Result<Light, SceneError> {
+    let light = object(value, key)?;
+    let kind = string(required(light, "type", key)?, &child(key, "type"))?;
+
+    let point = |name: &str| vector(required(light, name, key)?, &child(key, name));
+    let scalar = |name: &str| number(required(light, name, key)?, &child(key, name));
+    let direction = || {
+        let direction = point("direction")?;
+        if direction.near_zero() {
+            return Err(invalid(&child(key, "direction"), "must not be zero"));
+        }
+        Ok(direction)
+    };
+    let angle = |name: &str, angle: f64| {
+        if (0.0..180.0).contains(&angle) {
+            Ok(angle)
+        } else {
+            Err(invalid(
+                &child(key, name),
+                "must be at least 0 and below 180 degrees",
+            ))
+        }
+    };
+    let falloff = || match light.get("falloff") {
+        Some(value) => {
+            let falloff_key = child(key, "falloff");
+            let name = string(value, &falloff_key)?;
+            Falloff::from_name(name)
+                .ok_or_else(|| invalid(&falloff_key, &format!("unknown falloff '{}'", name)))
+        }
+        None => Ok(Falloff::InverseSquare),
+    };
+
+    match kind {
+        "point" => {
+            check_keys(light, &["type", "position", "intensity", "falloff"], key)?;
+            Ok(Light::Point {
+                position: point("position")?,
+                intensity: point("intensity")?,
+                falloff: falloff()?,
+            })
+        }
+        "spot" => {
+            check_keys(
+                light,
+                &[
+                    "type",
+                    "position",
+                    "direction",
+                    "intensity",
+                    "inner_angle",
+                    "outer_angle",
+                    "falloff",
+                ],
+                key,
+            )?;
+            let outer_angle = angle("outer_angle", scalar("outer_angle")?)?;
+            let inner_angle = match light.get("inner_angle") {
+                Some(value) => number(value, &child(key, "inner_angle"))?,
+                None => outer_angle,
+            };
+            if inner_angle > outer_angle {
+                return Err(invalid(
+                    &child(key, "inner_angle"),
+                    "must not be larger than outer_angle",
+                ));
+            }
+            Ok(Light::Spot {
+                position: point("position")?,
+                direction: direction()?,
+                intensity: point("intensity")?,
+                inner_angle,
+                outer_angle,
+                falloff: falloff()?,
+            })
+        }
+        "directional" => {
+            check_keys(
+                light,
+                &["type", "direction", "irradiance", "angular_diameter"],
+                key,
+            )?;
+            let angular_diameter = match light.get("angular_diameter") {
+                Some(value) => angle(
+                    "angular_diameter",
+                    number(value, &child(key, "angular_diameter"))?,
+                )?,
+                None => 0.0,
+            };
+            Ok(Light::Directional {
+                direction: direction()?,
+                irradiance: point("irradiance")?,
+                angular_diameter,
+            })
+        }
+        _ => Err(invalid(
+            &child(key, "type"),
+            &format!("unknown light type '{}'", kind),
+        )),
+    }
+}
+
+fn background_to_json(background: &BackgroundDescription) -> Value {
+    match background {
+        BackgroundDescription::Solid(color) => json!({
+            "type": "solid",
+            "color": vector_to_json(*color),
+        }),
+        BackgroundDescription::Gradient { bottom, top } => json!({
+            "type": "gradient",
+            "bottom": vector_to_json(*bottom),
+            "top": vector_to_json(*top),
+        }),
+        BackgroundDescription::Environment {
+            path,
+            rotation,
+            intensity,
+        } => json!({
+            "type": "environment",
+            "path": path.to_string_lossy(),
+            "rotation": rotation,
+            "intensity": intensity,
+        }),
+    }
+}
+
+fn material_to_json(material: &MaterialDescription) -> Value {
+    let (mut value, bump) = match material {
+        MaterialDescription::None => (json!({ "type": "none" }), &None),
+        MaterialDescription::Lambertian { albedo, bump } => (
+            json!({
+                "type": "lambertian",
+                "albedo": texture_to_json(albedo),
+            }),
+            bump,
+        ),
+        MaterialDescription::Metal { albedo, fuzz, bump } => (
+            json!({
+                "type": "metal",
+                "albedo": texture_to_json(albedo),
+                "fuzz": fuzz,
+            }),
+            bump,
+        ),
+        MaterialDescription::Dielectric {
+            index_of_refraction,
+            bump,
+        } => (
+            json!({
+                "type": "dielectric",
+                "index_of_refraction": index_of_refraction,
+            }),
+            bump,
+        ),
+        MaterialDescription::DiffuseLight { emit } => (
+            json!({
+                "type": "diffuse_light",
+                "emit": vector_to_json(*emit),
+            }),
+            &None,
+        ),
+    };
+
+    if let Some(bump) = bump {
+        value["bump"] = match bump {
+            BumpDescription::Normal { map, strength } => json!({
+                "type": "normal",
+                "map": texture_to_json(map),
+                "strength": strength,
+            }),
+            BumpDescription::Height { map, scale } => json!({
+                "type": "height",
+                "map": texture_to_json(map),
+                "scale": scale,
+            }),
+        };
+    }
+    value
+}
+
+fn texture_to_json(texture: &TextureDescription) -> Value {
+    match texture {
+        TextureDescription::Solid(color) => vector_to_json(*color),
+        TextureDescription::Checker { scale, odd, even } => json!({
+            "type": "checker",
+            "scale": scale,
+            "odd": texture_to_json(odd),
+            "even": texture_to_json(even),
+        }),
+        TextureDescription::Image { path, wrap } => json!({
+            "type": "image",
+            "path": path.to_string_lossy(),
+            "wrap": wrap.name(),
+        }),
+        TextureDescription::Noise {
+            pattern,
+            seed,
+            scale,
+            octaves,
+            low,
+            high,
+            space,
+        } => json!({
+            "type": "noise",
+            "pattern": pattern.name(),
+            "seed": seed,
+            "scale": scale,
+            "octaves": octaves,
+            "low": vector_to_json(*low),
+            "high": vector_to_json(*high),
+            "space": space.name(),
+        }),
+        TextureDescription::VertexColor { fallback } => json!({
+            "type": "vertex_color",
+            "fallback": texture_to_json(fallback),
+        }),
+    }
+}
+
+fn object_to_json(object: &ObjectDescription) -> Value {
+    match object {
+        ObjectDescription::Sphere {
+            center,
+            radius,
+            material,
+        } => json!({
+            "type": "sphere",
+            "center": vector_to_json(*center),
+            "radius": radius,
+            "material": material,
+        }),
+        ObjectDescription::MovingSphere {
+            center0,
+            center1,
+            time0,
+            time1,
+            radius,
+            material,
+        } => json!({
+            "type": "moving_sphere",
+            "center0": vector_to_json(*center0),
+            "center1": vector_to_json(*center1),
+            "time0": time0,
+            "time1": time1,
+            "radius": radius,
+            "material": material,
+        }),
+        ObjectDescription::Triangle {
+            v0,
+            v1,
+            v2,
+            material,
+        } => json!({
+            "type": "triangle",
+            "v0": vector_to_json(*v0),
+            "v1": vector_to_json(*v1),
+            "v2": vector_to_json(*v2),
+            "material": material,
+        }),
+        ObjectDescription::Quad { q, u, v, material } => json!({
+            "type": "quad",
+            "q": vector_to_json(*q),
+            "u": vector_to_json(*u),
+            "v": vector_to_json(*v),
+            "material": material,
+        }),
+        ObjectDescription::Cuboid { min, max, material } => json!({
+            "type": "cuboid",
+            "min": vector_to_json(*min),
+            "max": vector_to_json(*max),
+            "material": material,
+        }),
+        ObjectDescription::Mesh { path, material } => {
+            let mut value = json!({
+                "type": "mesh",
+                "path": path.to_string_lossy(),
+            });
+            if let Some(material) = material {
+                value["material"] = json!(material);
+            }
+            value
+        }
+    }
+}
+
+fn light_to_json(light: &Light) -> Value {
+    match light {
+        Light::Point {
+            position,
+            intensity,
+            falloff,
+        } => json!({
+            "type": "point",
+            "position": vector_to_json(*position),
+            "intensity": vector_to_json(*intensity),
+            "falloff": falloff.name(),
+        }),
+        Light::Spot {
+            position,
+            direction,
+            intensity,
+            inner_angle,
+            outer_angle,
+            falloff,
+        } => json!({
+            "type": "spot",
+            "position": vector_to_json(*position),
+            "direction": vector_to_json(*direction),
+            "intensity": vector_to_json(*intensity),
+            "inner_angle": inner_angle,
+            "outer_angle": outer_angle,
+            "falloff": falloff.name(),
+        }),
+        Light::Directional {
+            direction,
+            irradiance,
+            angular_diameter,
+        } => json!({
+            "type": "directional",
+            "direction": vector_to_json(*direction),
+            "irradiance": vector_to_json(*irradiance),
+            "angular_diameter": angular_diameter,
+        }),
+    }
+}
+
+fn vector_to_json(v: Vec3) -> Value {
+    json!([v.x, v.y, v.z])
+}
+
+fn child(key: &str, name: &str) -> String {
+    if key.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", key, name)
+    }
+}
+
+fn invalid(key: &str, message: &str) -> SceneError {
+    SceneError::Invalid {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn object<'a>(value: &'a Value, key: &str) -> Result<&'a Map<String, Value>, SceneError> {
+    value
+        .as_object()
+        .ok_or_else(|| invalid(key, "expected an object"))
+}
+
+fn required<'a>(
+    object: &'a Map<String, Value>,
+    name: &str,
+    key: &str,
+) -> Result<&'a Value, SceneError> {
+    object
+        .get(name)
+        .ok_or_else(|| invalid(&child(key, name), "missing required key"))
+}
+
+/// Rejects keys that are not in `allowed`, which catches misspelt options.
+fn check_keys(object: &Map<String, Value>, allowed: &[&str], key: &str) -> Result<(), SceneError> {
+    match object.keys().find(|name| !allowed.contains(&name.as_str())) {
+        Some(name) => Err(invalid(&child(key, name), "unknown key")),
+        None => Ok(()),
+    }
+}
+
+fn number(value: &Value, key: &str) -> Result<f64, SceneError> {
+    value
+        .as_f64()
+        .ok_or_else(|| invalid(key, "expected a number"))
+}
+
+fn unsigned(value: &Value, key: &str) -> Result<u64, SceneError> {
+    value
+        .as_u64()
+        .ok_or_else(|| invalid(key, "expected a non-negative integer"))
+}
+
+fn string<'a>(value: &'a Value, key: &str) -> Result<&'a str, SceneError> {
+    value
+        .as_str()
+        .ok_or_else(|| invalid(key, "expected a string"))
+}
+
+fn vector(value: &Value, key: &str) -> Result<Vec3, SceneError> {
+    match value.as_array().map(Vec::as_slice) {
+        Some([x, y, z]) => Ok(Vec3::new(number(x, key)?, number(y, key)?, number(z, key)?)),
+        _ => Err(invalid(key, "expected an array of 3 numbers")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use crate::bump::Bump;
+    use crate::hittable::{HitRecord, Hittable};
+    use crate::light::{Falloff, Light};
+    use crate::material::{Dielectric, Lambertian, Material, Metal};
+    use crate::ray::Ray;
+    use crate::scene_file::{
+        BackgroundDescription, BumpDescription, CameraDescription, ImageDescription,
+        MaterialDescription, ObjectDescription, SceneDescription, SceneError, TextureDescription,
+    };
+    use crate::texture::{Pattern, Space, WrapMode};
+    use crate::vec3::{Color, Vec3};
+
+    const SCENE: &str = r#"{
+        "image": { "width": 200, "height": 100, "samples_per_pixel": 10, "max_depth": 5, "seed": 3 },
+        "camera": { "look_from": [0, 0, 0], "look_at": [0, 0, -1], "vfov": 90, "shutter": [0, 1] },
+        "background": { "type": "solid", "color": [0.1, 0.1, 0.2] },
+        "materials": {
+            "ground": { "type": "lambertian", "albedo": {
+                "type": "checker", "scale": 2, "odd": [0.8, 0.8, 0.0], "even": [0.2, 0.3, 0.1]
+            } },
+            "gold": { "type": "metal", "albedo": [0.8, 0.6, 0.2], "fuzz": 0.1 },
+            "stone": { "type": "lambertian", "albedo": {
+                "type": "noise", "pattern": "marble", "scale": 4, "seed": 9, "space": "object"
+            }, "bump": {
+                "type": "height", "map": { "type": "noise", "pattern": "worley", "seed": 2 }, "scale": 0.02
+            } },
+            "glass": { "type": "dielectric", "index_of_refraction": 1.5, "bump": {
+                "type": "normal", "map": [0.5, 0.5, 1], "strength": 0.5
+            } },
+            "lamp": { "type": "diffuse_light", "emit": [4, 4, 4] }
+        },
+        "objects": [
+            { "type": "sphere", "center": [0, -100.5, -1], "radius": 100, "material": "ground" },
+            { "type": "moving_sphere", "center0": [1, 0, -1], "center1": [1, 0.5, -1],
+              "time0": 0, "time1": 1, "radius": 0.5, "material": "gold" },
+            { "type": "triangle", "v0": [-1, 0, -2], "v1": [0, 1, -2], "v2": [-1, 1, -2], "material": "glass" },
+            { "type": "quad", "q": [-1, 3, -2], "u": [2, 0, 0], "v": [0, 0, 1], "material": "lamp" },
+            { "type": "cuboid", "min": [2, 0, -3], "max": [3, 1, -2], "material": "ground" }
+        ],
+        "lights": [
+            { "type": "point", "position": [0, 2, -1], "intensity": [5, 5, 5] },
+            { "type": "spot", "position": [0, 2, -1], "direction": [0, -1, 0], "intensity": [3, 3, 3],
+              "inner_angle": 10, "outer_angle": 20, "falloff": "linear" },
+            { "type": "directional", "direction": [0, -1, -1], "irradiance": [1, 1, 1], "angular_diameter": 0.5 }
+        ]
+    }"#;
+
+    #[test]
+    fn parse_scene() {
+        let description = SceneDescription::parse(SCENE).unwrap();
+
+        assert_eq!(description.image.width, 200);
+        assert_eq!(description.image.seed, 3);
+        assert_eq!(description.camera.vup, Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(description.camera.shutter, (0.0, 1.0));
+        assert_eq!(
+            description.background,
+            BackgroundDescription::Solid(Color::new(0.1, 0.1, 0.2))
+        );
+        assert_eq!(description.materials.len(), 5);
+        assert!(matches!(
+            &description.materials["stone"],
+            MaterialDescription::Lambertian {
+                albedo: TextureDescription::Noise {
+                    pattern: Pattern::Marble,
+                    seed: 9,
+                    octaves: 7,
+                    space: Space::Object,
+                    ..
+                },
+                bump: Some(BumpDescription::Height { scale, .. }),
+            } if *scale == 0.02
+        ));
+        assert_eq!(
+            description.materials["glass"],
+            MaterialDescription::Dielectric {
+                index_of_refraction: 1.5,
+                bump: Some(BumpDescription::Normal {
+                    map: Color::new(0.5, 0.5, 1.0).into(),
+                    strength: 0.5,
+                }),
+            }
+        );
+        assert_eq!(description.objects.len(), 5);
+        assert_eq!(description.lights.len(), 3);
+        assert_eq!(
+            description.lights[0],
+            Light::Point {
+                position: Vec3::new(0.0, 2.0, -1.0),
+                intensity: Color::new(5.0, 5.0, 5.0),
+                falloff: Falloff::InverseSquare,
+            }
+        );
+        assert!(matches!(
+            description.lights[1],
+            Light::Spot { falloff: Falloff::Linear, inner_angle, .. } if inner_angle == 10.0
+        ));
+
+        let settings = description.settings();
+        assert_eq!(settings.image_width, 200);
+        assert_eq!(settings.samples_per_pixel, 10);
+
+        let camera = description.camera();
+        assert_eq!(camera.time1, 1.0);
+
+        let materials = description.materials().unwrap();
+        assert!(matches!(
+            &materials["stone"],
+            Material::Lambertian(Lambertian { bump: Some(_), .. })
+        ));
+        assert!(matches!(
+            &materials["gold"],
+            Material::Metal(Metal { bump: None, .. })
+        ));
+
+        let objects = description.objects().unwrap();
+        assert_eq!(objects.objects.len(), 5);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        let record = objects.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(matches!(record.material, Material::Lambertian(_)));
+
+        let ray = Ray::new(Vec3::new(0.0, 0.0, -1.5), Vec3::new(0.0, 1.0, 0.0));
+        let record = objects.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(matches!(record.material, Material::DiffuseLight(_)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let description = SceneDescription::parse(SCENE).unwrap();
+        let json = description.to_json();
+        let reparsed = SceneDescription::parse(&json).unwrap();
+
+        assert_eq!(reparsed.image, description.image);
+        assert_eq!(reparsed.camera, description.camera);
+        assert_eq!(reparsed.background, description.background);
+        assert_eq!(reparsed.objects, description.objects);
+        assert_eq!(reparsed.lights, description.lights);
+        assert_eq!(reparsed.to_json(), json);
+    }
+
+    #[test]
+    fn export_built_scene() {
+        let mut materials = BTreeMap::new();
+        materials.insert(
+            String::from("red"),
+            MaterialDescription::Lambertian {
+                albedo: TextureDescription::Checker {
+                    scale: 0.25,
+                    odd: Box::new(Color::new(1.0, 0.0, 0.0).into()),
+                    even: Box::new(TextureDescription::Image {
+                        path: PathBuf::from("bricks.png"),
+                        wrap: WrapMode::MirroredRepeat,
+                    }),
+                },
+                bump: Some(BumpDescription::Normal {
+                    map: TextureDescription::Image {
+                        path: PathBuf::from("bricks_normal.png"),
+                        wrap: WrapMode::Repeat,
+                    },
+                    strength: 2.0,
+                }),
+            },
+        );
+        let description = SceneDescription {
+            image: ImageDescription {
+                width: 64,
+                height: 64,
+                samples_per_pixel: 1,
+                max_depth: 2,
+                seed: 0,
+            },
+            camera: CameraDescription {
+                look_from: Vec3::new(0.0, 0.0, 5.0),
+                look_at: Vec3::new(0.0, 0.0, 0.0),
+                vup: Vec3::new(0.0, 1.0, 0.0),
+                vfov: 40.0,
+                aperture: 0.1,
+                focus_dist: Some(5.0),
+                shutter: (0.0, 0.0),
+            },
+            background: BackgroundDescription::Environment {
+                path: PathBuf::from("sky.hdr"),
+                rotation: 45.0,
+                intensity: 2.0,
+            },
+            materials,
+            objects: vec![ObjectDescription::Sphere {
+                center: Vec3::new(0.0, 0.0, 0.0),
+                radius: 1.0,
+                material: String::from("red"),
+            }],
+            lights: vec![Light::Directional {
+                direction: Vec3::new(1.0, -1.0, 0.0),
+                irradiance: Color::new(2.0, 2.0, 2.0),
+                angular_diameter: 0.0,
+            }],
+            base_directory: None,
+        };
+
+        let reparsed = SceneDescription::parse(&description.to_json()).unwrap();
+
+        assert_eq!(reparsed.camera, description.camera);
+        assert_eq!(reparsed.background, description.background);
+        assert_eq!(reparsed.objects, description.objects);
+        assert_eq!(reparsed.lights, description.lights);
+        assert_eq!(reparsed.materials, description.materials);
+    }
+
+    #[test]
+    fn objects_check_built_descriptions() {
+        let mut description = SceneDescription::parse(SCENE).unwrap();
+        description.objects = vec![ObjectDescription::Mesh {
+            path: PathBuf::from("scene.gltf"),
+            material: Some(String::from("gold")),
+        }];
+
+        match description.objects() {
+            Err(SceneError::Invalid { key, .. }) => assert_eq!(key, "objects[0].material"),
+            other => panic!("expected a material error, got {:?}", other.err()),
+        }
+
+        description.objects = vec![ObjectDescription::Sphere {
+            center: Vec3::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+            material: String::from("missing"),
+        }];
+        match description.objects() {
+            Err(SceneError::Invalid { key, .. }) => assert_eq!(key, "objects[0].material"),
+            other => panic!("expected a material error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn image_textures_load_relative_to_scene() {
+        let directory = std::env::temp_dir().join(format!(
+            "raytracing_{}_image_textures_load_relative_to_scene",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        image::RgbImage::from_pixel(1, 1, image::Rgb([255, 255, 255]))
+            .save(directory.join("white.png"))
+            .unwrap();
+        image::RgbImage::from_pixel(1, 1, image::Rgb([51, 102, 255]))
+            .save(directory.join("normal.png"))
+            .unwrap();
+        let source = SCENE
+            .replacen(
+                "\"even\": [0.2, 0.3, 0.1]",
+                "\"even\": { \"type\": \"image\", \"path\": \"white.png\" }",
+                1,
+            )
+            .replacen(
+                "\"map\": [0.5, 0.5, 1]",
+                "\"map\": { \"type\": \"image\", \"path\": \"normal.png\" }",
+                1,
+            );
+        let path = directory.join("scene.json");
+        std::fs::write(&path, &source).unwrap();
+
+        let description = SceneDescription::load(&path).unwrap();
+        let materials = description.materials().unwrap();
+        let Material::Lambertian(ground) = &materials["ground"] else {
+            panic!("ground should be lambertian");
+        };
+        let mut record = HitRecord::new_empty();
+        (record.u, record.v) = (0.5, 0.5);
+        record.p = Vec3::new(0.5, 0.5, 0.5);
+        let white = ground.albedo.value(&record);
+        assert!((white - Color::new(1.0, 1.0, 1.0)).length() < 1e-9);
+
+        // Normal maps are data, so their images skip sRGB decoding.
+        let Material::Dielectric(Dielectric {
+            bump: Some(Bump::Normal { map, .. }),
+            ..
+        }) = &materials["glass"]
+        else {
+            panic!("glass should have a normal map");
+        };
+        assert!((map.value(&record) - Color::new(0.2, 0.4, 1.0)).length() < 1e-9);
+
+        std::fs::remove_file(directory.join("white.png")).unwrap();
+        match description.objects() {
+            Err(SceneError::Invalid { key, .. }) => {
+                assert_eq!(key, "materials.ground.albedo.even.path")
+            }
+            other => panic!("expected a missing texture error, got {:?}", other.err()),
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    fn error_key(source: &str) -> String {
+        match SceneDescription::parse(source) {
+            Err(SceneError::Invalid { key, .. }) => key,
+            other => panic!("expected an invalid key error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn errors_point_to_key() {
+        let replace = |from: &str, to: &str| SCENE.replacen(from, to, 1);
+
+        assert_eq!(
+            error_key(&replace(
+                "\"material\": \"gold\"",
+                "\"material\": \"silver\""
+            )),
+            "objects[1].material"
+        );
+        assert_eq!(
+            error_key(&replace("\"fuzz\": 0.1", "\"fuzz\": \"high\"")),
+            "materials.gold.fuzz"
+        );
+        assert_eq!(
+            error_key(&replace(
+                "\"radius\": 100",
+                "\"radius\": 100, \"radios\": 1"
+            )),
+            "objects[0].radios"
+        );
+        assert_eq!(
+            error_key(&replace("\"look_at\": [0, 0, -1]", "\"look_at\": [0, -1]")),
+            "camera.look_at"
+        );
+        assert_eq!(
+            error_key(&replace(
+                "\"look_at\": [0, 0, -1]",
+                "\"look_at\": [0, 0, 0]"
+            )),
+            "camera.look_at"
+        );
+        assert_eq!(
+            error_key(&replace(
+                "\"vfov\": 90,",
+                "\"vup\": [0, 0, 2], \"vfov\": 90,"
+            )),
+            "camera.vup"
+        );
+        assert_eq!(
+            error_key(&replace("\"vfov\": 90", "\"vfov\": 180")),
+            "camera.vfov"
+        );
+        assert_eq!(
+            error_key(&replace("\"vfov\": 90", "\"vfov\": 90, \"focus_dist\": 0")),
+            "camera.focus_dist"
+        );
+        assert_eq!(error_key(&replace("\"width\": 200, ", "")), "image.width");
+        assert_eq!(
+            error_key(&replace(
+                "\"samples_per_pixel\": 10",
+                "\"samples_per_pixel\": 0"
+            )),
+            "image.samples_per_pixel"
+        );
+        assert_eq!(
+            error_key(&replace("\"height\": 100", "\"height\": 4294967296")),
+            "image.height"
+        );
+        assert_eq!(
+            error_key(&replace("\"type\": \"triangle\"", "\"type\": \"cube\"")),
+            "objects[2].type"
+        );
+        assert_eq!(
+            error_key(&replace("\"v\": [0, 0, 1]", "\"v\": [-4, 0, 0]")),
+            "objects[3].u"
+        );
+        assert_eq!(
+            error_key(&replace("\"max\": [3, 1, -2]", "\"max\": [2, 1, -2]")),
+            "objects[4].max"
+        );
+        assert_eq!(
+            error_key(&replace(
+                "\"type\": \"cuboid\", \"min\": [2, 0, -3], \"max\": [3, 1, -2]",
+                "\"type\": \"mesh\", \"path\": \"teapot.obj\""
+            )),
+            "objects[4].material"
+        );
+        assert_eq!(
+            error_key(&replace("\"type\": \"solid\"", "\"type\": \"sky\"")),
+            "background.type"
+        );
+        assert_eq!(
+            error_key(&replace(
+                "\"falloff\": \"linear\"",
+                "\"falloff\": \"cubic\""
+            )),
+            "lights[1].falloff"
+        );
+        assert_eq!(
+            error_key(&replace("\"inner_angle\": 10", "\"inner_angle\": 30")),
+            "lights[1].inner_angle"
+        );
+        assert_eq!(
+            error_key(&replace(
+                "\"direction\": [0, -1, 0]",
+                "\"direction\": [0, 0, 0]"
+            )),
+            "lights[1].direction"
+        );
+        assert_eq!(
+            error_key(&replace("\"outer_angle\": 20", "\"outer_angle\": 180")),
+            "lights[1].outer_angle"
+        );
+        assert_eq!(
+            error_key(&replace(
+                "\"angular_diameter\": 0.5",
+                "\"angular_diameter\": -1"
+            )),
+            "lights[2].angular_diameter"
+        );
+        assert_eq!(
+            error_key(&replace("\"type\": \"height\"", "\"type\": \"bumpy\"")),
+            "materials.stone.bump.type"
+        );
+        assert_eq!(
+            error_key(&replace(", \"scale\": 0.02", "")),
+            "materials.stone.bump.scale"
+        );
+    }
+
+    #[test]
+    fn example_scene() {
+        let description =
+            SceneDescription::parse(include_str!("../scenes/three_spheres.json")).unwrap();
+
+        assert_eq!(description.objects.len(), 5);
+        assert_eq!(description.objects().unwrap().objects.len(), 5);
+    }
+}
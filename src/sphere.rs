@@ -1,7 +1,12 @@
+use std::f64::consts::PI;
+
+use crate::aabb::Aabb;
 use crate::hittable::{HitRecord, Hittable};
 use crate::material::Material;
+use crate::onb::Onb;
+use crate::random::random_double;
 use crate::ray::Ray;
-use crate::vec3::Vec3;
+use crate::vec3::{Point3, Vec3};
 
 pub struct Sphere {
     pub center: Vec3,
@@ -20,42 +25,134 @@ impl Sphere {
 }
 
 impl Hittable for Sphere {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let mut empty_record = HitRecord::new_empty();
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        hit_sphere(self.center, self.radius, &self.material, r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(sphere_box(self.center, self.radius))
+    }
 
-        let oc = r.origin - self.center;
-        let a = r.direction.length_squared();
-        let half_b = oc.dot(r.direction);
-        let c = oc.length_squared() - self.radius * self.radius;
+    fn is_light(&self) -> bool {
+        matches!(self.material, Material::DiffuseLight(_))
+    }
 
-        let discriminant = half_b * half_b - a * c;
-        if discriminant < 0.0 {
-            return None;
+    /// Uniform over the cone of directions the sphere covers, so zero from inside.
+    fn pdf_value(&self, origin: Point3, direction: Vec3) -> f64 {
+        let distance_squared = (self.center - origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return 0.0;
+        }
+        if self
+            .hit(&Ray::new(origin, direction), 0.001, f64::INFINITY)
+            .is_none()
+        {
+            return 0.0;
+        }
+
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+    }
+
+    fn random(&self, origin: Point3) -> Vec3 {
+        let direction = self.center - origin;
+        let distance_squared = direction.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return direction;
         }
 
-        let square_root_d = discriminant.sqrt();
-        let mut root = (-half_b - square_root_d) / a;
+        let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
+        let z = 1.0 + random_double() * (cos_theta_max - 1.0);
+        let phi = 2.0 * PI * random_double();
+        let sin_theta = (1.0 - z * z).sqrt();
 
+        Onb::from_w(direction).local(Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+    }
+}
+
+pub(crate) fn hit_sphere<'material>(
+    center: Point3,
+    radius: f64,
+    material: &'material Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'material>> {
+    let mut empty_record = HitRecord::new_empty();
+
+    let oc = r.origin - center;
+    let a = r.direction.length_squared();
+    let half_b = oc.dot(r.direction);
+    let c = oc.length_squared() - radius * radius;
+
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let square_root_d = discriminant.sqrt();
+    let mut root = (-half_b - square_root_d) / a;
+
+    if root < t_min || t_max < root {
+        root = (-half_b + square_root_d) / a;
         if root < t_min || t_max < root {
-            root = (-half_b + square_root_d) / a;
-            if root < t_min || t_max < root {
-                return None;
-            }
+            return None;
         }
+    }
 
-        empty_record.t = root;
-        empty_record.p = r.at(empty_record.t);
-        let outward_normal = (empty_record.p - self.center) / self.radius;
-        empty_record.set_face_normal(r, outward_normal);
-        empty_record.material = &self.material;
+    empty_record.t = root;
+    empty_record.p = r.at(empty_record.t);
+    empty_record.local = empty_record.p - center;
+    let outward_normal = (empty_record.p - center) / radius;
+    empty_record.set_face_normal(r, outward_normal);
+    (empty_record.u, empty_record.v) = sphere_uv(outward_normal);
+    (empty_record.tangent, empty_record.bitangent) = sphere_tangents(empty_record.local);
+    empty_record.material = material;
 
-        Some(empty_record)
+    Some(empty_record)
+}
+
+/// Longitude and latitude of a point `p` on the unit sphere, both in `0..1`. `u` starts
+/// at -x and runs towards +z; `v` runs from the south pole to the north pole.
+pub(crate) fn sphere_uv(p: Point3) -> (f64, f64) {
+    let theta = (-p.y).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z).atan2(p.x) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// Derivatives along `u` and `v` of the `sphere_uv` parameterization at `local`, a
+/// point relative to the center. Both vanish at the poles.
+fn sphere_tangents(local: Vec3) -> (Vec3, Vec3) {
+    let rho = (local.x * local.x + local.z * local.z).sqrt();
+    if rho <= 0.0 {
+        return (Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 0.0));
     }
+
+    let tangent = 2.0 * PI * Vec3::new(local.z, 0.0, -local.x);
+    let bitangent = PI * Vec3::new(-local.y * local.x / rho, rho, -local.y * local.z / rho);
+    (tangent, bitangent)
+}
+
+pub(crate) fn sphere_box(center: Point3, radius: f64) -> Aabb {
+    let r = radius.abs();
+    let radius = Vec3::new(r, r, r);
+    Aabb::new(center - radius, center + radius)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{material::Material, sphere::Sphere, vec3::Vec3};
+    use std::f64::consts::PI;
+
+    use crate::{
+        hittable::Hittable,
+        material::{DiffuseLight, Material},
+        random,
+        ray::Ray,
+        sphere::{sphere_uv, Sphere},
+        vec3::{Color, Vec3},
+    };
 
     #[test]
     fn new_sphere() {
@@ -64,4 +161,56 @@ mod tests {
         assert_eq!(sphere.center, Vec3::new(10.0, 11.0, 12.0));
         assert_eq!(sphere.radius, 50.0);
     }
+
+    #[test]
+    fn spherical_uvs() {
+        let close = |(u, v): (f64, f64), expected: (f64, f64)| {
+            (u - expected.0).abs() < 1e-9 && (v - expected.1).abs() < 1e-9
+        };
+
+        assert!(close(sphere_uv(Vec3::new(-1.0, 0.0, 0.0)), (0.0, 0.5)));
+        assert!(close(sphere_uv(Vec3::new(0.0, 0.0, 1.0)), (0.25, 0.5)));
+        assert!(close(sphere_uv(Vec3::new(1.0, 0.0, 0.0)), (0.5, 0.5)));
+        assert!(close(sphere_uv(Vec3::new(0.0, 0.0, -1.0)), (0.75, 0.5)));
+        assert!((sphere_uv(Vec3::new(0.0, 1.0, 0.0)).1 - 1.0).abs() < 1e-9);
+
+        let sphere = Sphere::new(Vec3::new(0.0, 0.0, -3.0), 2.0, Material::None);
+        let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0));
+        let record = sphere.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(close((record.u, record.v), (0.25, 0.5)));
+
+        // At the +z point u grows towards +x and v towards the north pole, over a
+        // circumference and half a circumference of the radius 2 sphere.
+        assert!((record.tangent - Vec3::new(4.0 * PI, 0.0, 0.0)).near_zero());
+        assert!((record.bitangent - Vec3::new(0.0, 2.0 * PI, 0.0)).near_zero());
+    }
+
+    #[test]
+    fn sample_sphere_light() {
+        let light = Sphere::new(
+            Vec3::new(0.0, 0.0, -2.0),
+            1.0,
+            Material::DiffuseLight(DiffuseLight::new(Color::new(1.0, 1.0, 1.0))),
+        );
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        assert!(light.is_light());
+
+        // The sphere covers a cone with a half angle of 30 degrees.
+        let expected = 1.0 / (2.0 * PI * (1.0 - (3.0f64).sqrt() / 2.0));
+        random::seed(1);
+        for _ in 0..100 {
+            let direction = light.random(origin);
+            assert!(light
+                .hit(&Ray::new(origin, direction), 0.001, f64::INFINITY)
+                .is_some());
+            assert!((light.pdf_value(origin, direction) - expected).abs() < 1e-9);
+        }
+
+        assert_eq!(light.pdf_value(origin, Vec3::new(0.0, 1.0, 0.0)), 0.0);
+        assert_eq!(
+            light.pdf_value(Vec3::new(0.0, 0.0, -2.5), Vec3::new(0.0, 1.0, 0.0)),
+            0.0
+        );
+        assert!(!Sphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0, Material::None).is_light());
+    }
 }
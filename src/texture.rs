@@ -0,0 +1,577 @@
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+
+use image::codecs::hdr::HdrDecoder;
+use image::{ColorType, ImageResult};
+
+use crate::hittable::HitRecord;
+use crate::noise::{Perlin, Worley};
+use crate::tonemap::srgb_to_linear;
+use crate::vec3::{Color, Point3};
+
+/// A color that varies over a surface.
+pub trait Texture: fmt::Debug + Send + Sync {
+    /// Color at a hit, looked up by its surface coordinates `u` and `v` or by its
+    /// position.
+    fn value(&self, rec: &HitRecord) -> Color;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> SolidColor {
+        SolidColor { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _rec: &HitRecord) -> Color {
+        self.color
+    }
+}
+
+/// Alternates between two textures in cubes of side `scale` filling space, so it
+/// needs no surface coordinates.
+#[derive(Debug, Clone)]
+pub struct Checker {
+    pub scale: f64,
+    pub odd: Arc<dyn Texture>,
+    pub even: Arc<dyn Texture>,
+}
+
+impl Checker {
+    pub fn new(scale: f64, odd: Arc<dyn Texture>, even: Arc<dyn Texture>) -> Checker {
+        Checker { scale, odd, even }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, rec: &HitRecord) -> Color {
+        let p = rec.p;
+        let cell =
+            (p.x / self.scale).floor() + (p.y / self.scale).floor() + (p.z / self.scale).floor();
+        if (cell as i64).rem_euclid(2) == 0 {
+            self.even.value(rec)
+        } else {
+            self.odd.value(rec)
+        }
+    }
+}
+
+/// The interpolated vertex color of meshes that have them, such as colored PLY scans,
+/// and `fallback` everywhere else.
+#[derive(Debug, Clone)]
+pub struct VertexColor {
+    pub fallback: Arc<dyn Texture>,
+}
+
+impl VertexColor {
+    pub fn new(fallback: Arc<dyn Texture>) -> VertexColor {
+        VertexColor { fallback }
+    }
+}
+
+impl Texture for VertexColor {
+    fn value(&self, rec: &HitRecord) -> Color {
+        match rec.color {
+            Some(color) => color,
+            None => self.fallback.value(rec),
+        }
+    }
+}
+
+/// What an image texture shows outside the `0..1` coordinate range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    /// Stretches the edge pixels.
+    Clamp,
+}
+
+impl WrapMode {
+    pub fn from_name(name: &str) -> Option<WrapMode> {
+        match name {
+            "repeat" => Some(WrapMode::Repeat),
+            "mirrored_repeat" => Some(WrapMode::MirroredRepeat),
+            "clamp" => Some(WrapMode::Clamp),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WrapMode::Repeat => "repeat",
+            WrapMode::MirroredRepeat => "mirrored_repeat",
+            WrapMode::Clamp => "clamp",
+        }
+    }
+
+    /// Maps a pixel index that may lie outside the image back into `0..size`.
+    fn apply(&self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let index = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::MirroredRepeat => {
+                let index = index.rem_euclid(2 * size);
+                if index < size {
+                    index
+                } else {
+                    2 * size - 1 - index
+                }
+            }
+            WrapMode::Clamp => index.clamp(0, size - 1),
+        };
+        index as usize
+    }
+}
+
+/// A bilinearly filtered image of linear colors. `v` runs from the bottom row up,
+/// as in OBJ texture coordinates.
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+    pub wrap: WrapMode,
+}
+
+impl ImageTexture {
+    /// Wraps `pixels`, stored row by row from the top.
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>, wrap: WrapMode) -> ImageTexture {
+        assert_eq!(pixels.len(), width * height, "texture size mismatch");
+        assert!(width > 0 && height > 0, "empty texture");
+
+        ImageTexture {
+            width,
+            height,
+            pixels,
+            wrap,
+        }
+    }
+
+    /// Loads any format `load_linear` reads.
+    pub fn load(path: impl AsRef<Path>, wrap: WrapMode) -> ImageResult<ImageTexture> {
+        let (width, height, pixels) = load_linear(path)?;
+        Ok(ImageTexture::new(width, height, pixels, wrap))
+    }
+
+    /// Loads an image holding data rather than colors, such as a normal or height
+    /// map, so 8-bit values are scaled to `0..1` without sRGB decoding.
+    pub fn load_data(path: impl AsRef<Path>, wrap: WrapMode) -> ImageResult<ImageTexture> {
+        let (width, height, pixels) = load_pixels(path, false)?;
+        Ok(ImageTexture::new(width, height, pixels, wrap))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = self.wrap.apply(x, self.width);
+        let y = self.wrap.apply(y, self.height);
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, rec: &HitRecord) -> Color {
+        // Pixel centers sit at half-integer coordinates.
+        let x = rec.u * self.width as f64 - 0.5;
+        let y = (1.0 - rec.v) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        // Casting saturates for huge coordinates, so the neighbours must not overflow.
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let (x1, y1) = (x0.saturating_add(1), y0.saturating_add(1));
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x1, y0) * fx;
+        let bottom = self.texel(x0, y1) * (1.0 - fx) + self.texel(x1, y1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+/// Which point procedural textures are evaluated at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Space {
+    World,
+    /// Relative to the object, so the pattern moves with it.
+    Object,
+}
+
+impl Space {
+    pub fn from_name(name: &str) -> Option<Space> {
+        match name {
+            "world" => Some(Space::World),
+            "object" => Some(Space::Object),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Space::World => "world",
+            Space::Object => "object",
+        }
+    }
+}
+
+/// Procedural patterns, each producing a value between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    /// Plain Perlin noise.
+    Noise,
+    /// Fractal sum of noise octaves.
+    Turbulence,
+    /// Stripes along z, bent by turbulence.
+    Marble,
+    /// Rings around the y axis, bent by turbulence.
+    Wood,
+    /// Distance to the nearest cell point.
+    Worley,
+}
+
+impl Pattern {
+    pub fn from_name(name: &str) -> Option<Pattern> {
+        match name {
+            "noise" => Some(Pattern::Noise),
+            "turbulence" => Some(Pattern::Turbulence),
+            "marble" => Some(Pattern::Marble),
+            "wood" => Some(Pattern::Wood),
+            "worley" => Some(Pattern::Worley),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Noise => "noise",
+            Pattern::Turbulence => "turbulence",
+            Pattern::Marble => "marble",
+            Pattern::Wood => "wood",
+            Pattern::Worley => "worley",
+        }
+    }
+}
+
+/// Blends from `low` to `high` by a procedural `pattern`, evaluated at the hit
+/// point scaled by `scale`. The same seed always gives the same pattern.
+#[derive(Debug, Clone)]
+pub struct NoiseTexture {
+    pub pattern: Pattern,
+    pub scale: f64,
+    /// Noise layers summed for turbulence, marble and wood.
+    pub octaves: usize,
+    pub low: Color,
+    pub high: Color,
+    pub space: Space,
+    seed: u64,
+    perlin: Perlin,
+    worley: Worley,
+}
+
+impl NoiseTexture {
+    /// A black to white pattern at unit scale in world space.
+    pub fn new(pattern: Pattern, seed: u64) -> NoiseTexture {
+        NoiseTexture {
+            pattern,
+            scale: 1.0,
+            octaves: 7,
+            low: Color::new(0.0, 0.0, 0.0),
+            high: Color::new(1.0, 1.0, 1.0),
+            space: Space::World,
+            seed,
+            perlin: Perlin::new(seed),
+            worley: Worley::new(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The pattern's value at `p`, between 0 and 1.
+    pub fn amount(&self, p: Point3) -> f64 {
+        let p = p * self.scale;
+        let amount = match self.pattern {
+            Pattern::Noise => 0.5 * (1.0 + self.perlin.noise(p)),
+            Pattern::Turbulence => self.perlin.turbulence(p, self.octaves),
+            Pattern::Marble => {
+                0.5 * (1.0 + (p.z + 10.0 * self.perlin.turbulence(p, self.octaves)).sin())
+            }
+            Pattern::Wood => {
+                let radius = (p.x * p.x + p.z * p.z).sqrt();
+                let rings = radius + 0.5 * self.perlin.turbulence(p, self.octaves);
+                rings - rings.floor()
+            }
+            Pattern::Worley => self.worley.noise(p),
+        };
+        amount.clamp(0.0, 1.0)
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, rec: &HitRecord) -> Color {
+        let p = match self.space {
+            Space::World => rec.p,
+            Space::Object => rec.local,
+        };
+        let amount = self.amount(p);
+        self.low * (1.0 - amount) + self.high * amount
+    }
+}
+
+/// Loads an image as linear colors, row by row from the top. Reads Radiance `.hdr`,
+/// OpenEXR and any 8-bit format the `image` crate supports, assuming 8-bit images
+/// are sRGB encoded.
+pub fn load_linear(path: impl AsRef<Path>) -> ImageResult<(usize, usize, Vec<Color>)> {
+    load_pixels(path, true)
+}
+
+fn load_pixels(path: impl AsRef<Path>, srgb: bool) -> ImageResult<(usize, usize, Vec<Color>)> {
+    let path = path.as_ref();
+    let is_hdr = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("hdr"));
+
+    // The generic reader quantizes Radiance files to 8 bits, so decode them directly.
+    if is_hdr {
+        let decoder = HdrDecoder::new(BufReader::new(File::open(path)?))?;
+        let metadata = decoder.metadata();
+        let pixels = decoder
+            .read_image_hdr()?
+            .iter()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0;
+                Color::new(r as f64, g as f64, b as f64)
+            })
+            .collect();
+        return Ok((metadata.width as usize, metadata.height as usize, pixels));
+    }
+
+    let image = image::open(path)?;
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let pixels = match image.color() {
+        ColorType::Rgb32F | ColorType::Rgba32F => image
+            .to_rgb32f()
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0;
+                Color::new(r as f64, g as f64, b as f64)
+            })
+            .collect(),
+        _ => image
+            .to_rgb8()
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0.map(|c| {
+                    let c = c as f64 / 255.0;
+                    if srgb {
+                        srgb_to_linear(c)
+                    } else {
+                        c
+                    }
+                });
+                Color::new(r, g, b)
+            })
+            .collect(),
+    };
+
+    Ok((width, height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use image::{Rgb, RgbImage};
+
+    use crate::hittable::HitRecord;
+    use crate::texture::{
+        Checker, ImageTexture, NoiseTexture, Pattern, SolidColor, Space, Texture, VertexColor,
+        WrapMode,
+    };
+    use crate::vec3::{Color, Point3};
+
+    fn record(u: f64, v: f64, p: Point3) -> HitRecord<'static> {
+        let mut record = HitRecord::new_empty();
+        (record.u, record.v) = (u, v);
+        record.p = p;
+        record.local = p;
+        record
+    }
+
+    #[test]
+    fn checker_alternates_in_space() {
+        let checker = Checker::new(
+            1.0,
+            Arc::new(SolidColor::new(Color::new(1.0, 1.0, 1.0))),
+            Arc::new(SolidColor::new(Color::new(0.0, 0.0, 0.0))),
+        );
+        let at = |x, y, z| checker.value(&record(0.0, 0.0, Point3::new(x, y, z))).x;
+
+        assert_eq!(at(0.5, 0.5, 0.5), 0.0);
+        assert_eq!(at(1.5, 0.5, 0.5), 1.0);
+        assert_eq!(at(1.5, 1.5, 0.5), 0.0);
+        assert_eq!(at(-0.5, 0.5, 0.5), 1.0);
+    }
+
+    #[test]
+    fn image_huge_coordinates() {
+        for wrap in [WrapMode::Repeat, WrapMode::MirroredRepeat, WrapMode::Clamp] {
+            let texture = gradient(wrap);
+            for u in [1e300, -1e300, f64::INFINITY] {
+                texture.value(&record(u, u, Point3::new(0.0, 0.0, 0.0)));
+            }
+        }
+    }
+
+    #[test]
+    fn vertex_color_falls_back() {
+        let texture = VertexColor::new(Arc::new(SolidColor::new(Color::new(0.8, 0.8, 0.8))));
+        let mut record = record(0.0, 0.0, Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(texture.value(&record), Color::new(0.8, 0.8, 0.8));
+
+        record.color = Some(Color::new(1.0, 0.5, 0.0));
+        assert_eq!(texture.value(&record), Color::new(1.0, 0.5, 0.0));
+    }
+
+    fn gradient(wrap: WrapMode) -> ImageTexture {
+        // Two columns, black on the left and white on the right.
+        let pixels = vec![
+            Color::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(0.0, 0.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ];
+        ImageTexture::new(2, 2, pixels, wrap)
+    }
+
+    #[test]
+    fn image_bilinear_filtering() {
+        let texture = gradient(WrapMode::Clamp);
+        let at = |u| texture.value(&record(u, 0.5, Point3::new(0.0, 0.0, 0.0))).x;
+
+        assert_eq!(at(0.25), 0.0);
+        assert_eq!(at(0.5), 0.5);
+        assert_eq!(at(0.75), 1.0);
+        assert_eq!(at(0.0), 0.0);
+        assert_eq!(at(1.0), 1.0);
+    }
+
+    #[test]
+    fn image_wrap_modes() {
+        let at = |wrap, u| {
+            let origin = Point3::new(0.0, 0.0, 0.0);
+            gradient(wrap).value(&record(u, 0.5, origin)).x
+        };
+
+        // Repeating blends the right edge into the left column again.
+        assert_eq!(at(WrapMode::Repeat, 1.0), 0.5);
+        assert_eq!(at(WrapMode::Repeat, 1.25), 0.0);
+        assert_eq!(at(WrapMode::MirroredRepeat, 1.0), 1.0);
+        assert_eq!(at(WrapMode::MirroredRepeat, 1.25), 1.0);
+        assert_eq!(at(WrapMode::MirroredRepeat, 1.75), 0.0);
+        assert_eq!(at(WrapMode::Clamp, 3.0), 1.0);
+
+        assert_eq!(
+            WrapMode::from_name("mirrored_repeat"),
+            Some(WrapMode::MirroredRepeat)
+        );
+        assert_eq!(
+            WrapMode::from_name(WrapMode::Clamp.name()),
+            Some(WrapMode::Clamp)
+        );
+        assert_eq!(WrapMode::from_name("border"), None);
+    }
+
+    #[test]
+    fn load_image_texture() {
+        let mut image = RgbImage::new(2, 1);
+        image.put_pixel(0, 0, Rgb([255, 0, 0]));
+        image.put_pixel(1, 0, Rgb([0, 51, 255]));
+        let path = std::env::temp_dir().join(format!(
+            "raytracing_{}_load_image_texture.png",
+            std::process::id()
+        ));
+        image.save(&path).unwrap();
+
+        let texture = ImageTexture::load(&path, WrapMode::Clamp).unwrap();
+        assert_eq!((texture.width(), texture.height()), (2, 1));
+        let left = texture.value(&record(0.0, 0.5, Point3::new(0.0, 0.0, 0.0)));
+        assert_eq!(left, Color::new(1.0, 0.0, 0.0));
+        let right = texture.value(&record(1.0, 0.5, Point3::new(0.0, 0.0, 0.0)));
+        assert!(right.y < 0.05);
+
+        // Data images keep their values as stored.
+        let data = ImageTexture::load_data(&path, WrapMode::Clamp).unwrap();
+        let right = data.value(&record(1.0, 0.5, Point3::new(0.0, 0.0, 0.0)));
+        assert_eq!(right, Color::new(0.0, 0.2, 1.0));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn noise_patterns() {
+        for pattern in [
+            Pattern::Noise,
+            Pattern::Turbulence,
+            Pattern::Marble,
+            Pattern::Wood,
+            Pattern::Worley,
+        ] {
+            let texture = |seed| {
+                let mut texture = NoiseTexture::new(pattern, seed);
+                texture.scale = 3.0;
+                texture.low = Color::new(1.0, 0.0, 0.0);
+                texture.high = Color::new(0.0, 0.0, 1.0);
+                texture
+            };
+            let (texture, same, other) = (texture(5), texture(5), texture(6));
+
+            let mut values = vec![];
+            let mut differs = false;
+            for i in 0..50 {
+                let p = Point3::new(i as f64 * 0.173, (i as f64 * 0.37).cos(), i as f64 * 0.05);
+                let color = texture.value(&record(0.0, 0.0, p));
+                // Always a blend of the two colors.
+                assert!((color.x + color.z - 1.0).abs() < 1e-9);
+                assert_eq!(color, same.value(&record(0.0, 0.0, p)));
+                differs |= color != other.value(&record(0.0, 0.0, p));
+                values.push(color.z);
+            }
+            assert!(differs, "{:?} ignores the seed", pattern);
+            let spread = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+                - values.iter().cloned().fold(f64::INFINITY, f64::min);
+            assert!(spread > 0.2, "{:?} is nearly constant", pattern);
+            assert_eq!(Pattern::from_name(pattern.name()), Some(pattern));
+        }
+        assert_eq!(Pattern::from_name("plasma"), None);
+    }
+
+    #[test]
+    fn noise_in_object_space() {
+        let mut texture = NoiseTexture::new(Pattern::Marble, 2);
+        let mut hit = record(0.0, 0.0, Point3::new(10.3, 0.7, 4.2));
+        hit.local = Point3::new(0.3, 0.7, 0.2);
+
+        let world = texture.value(&hit);
+        assert_eq!(world, texture.value(&record(0.0, 0.0, hit.p)));
+        texture.space = Space::Object;
+        assert_eq!(
+            texture.value(&hit),
+            texture.value(&record(0.0, 0.0, hit.local))
+        );
+        assert_eq!(Space::from_name("object"), Some(Space::Object));
+    }
+}
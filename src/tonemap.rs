@@ -0,0 +1,198 @@
+use crate::vec3::Color;
+
+/// Curve used to compress scene radiance into the displayable `0..1` range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    /// Clips everything above 1.0.
+    Clamp,
+    Reinhard,
+    /// Reinhard scaled so `white_point` maps to 1.0 instead of infinity.
+    ReinhardExtended {
+        white_point: f64,
+    },
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+    /// John Hable's Uncharted 2 filmic curve.
+    Hable,
+}
+
+impl Operator {
+    pub fn from_name(name: &str, white_point: f64) -> Option<Operator> {
+        match name.to_ascii_lowercase().as_str() {
+            "clamp" | "none" => Some(Operator::Clamp),
+            "reinhard" => Some(Operator::Reinhard),
+            "reinhard-extended" => Some(Operator::ReinhardExtended { white_point }),
+            "aces" => Some(Operator::Aces),
+            "hable" | "uncharted" => Some(Operator::Hable),
+            _ => None,
+        }
+    }
+
+    pub fn map(&self, x: f64) -> f64 {
+        let x = x.max(0.0);
+        match *self {
+            Operator::Clamp => x,
+            Operator::Reinhard => x / (1.0 + x),
+            Operator::ReinhardExtended { white_point } => {
+                x * (1.0 + x / (white_point * white_point)) / (1.0 + x)
+            }
+            Operator::Aces => (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14),
+            Operator::Hable => {
+                const EXPOSURE_BIAS: f64 = 2.0;
+                const WHITE_POINT: f64 = 11.2;
+                hable_partial(x * EXPOSURE_BIAS) / hable_partial(WHITE_POINT)
+            }
+        }
+        .min(1.0)
+    }
+}
+
+fn hable_partial(x: f64) -> f64 {
+    const A: f64 = 0.15;
+    const B: f64 = 0.50;
+    const C: f64 = 0.10;
+    const D: f64 = 0.20;
+    const E: f64 = 0.02;
+    const F: f64 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+/// Turns linear radiance into display colors: exposure, then the operator applied
+/// per channel, then the sRGB transfer function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapping {
+    /// Exposure adjustment in stops, each one doubling the brightness.
+    pub exposure: f64,
+    pub operator: Operator,
+}
+
+impl Default for ToneMapping {
+    fn default() -> ToneMapping {
+        ToneMapping {
+            exposure: 0.0,
+            operator: Operator::Clamp,
+        }
+    }
+}
+
+impl ToneMapping {
+    pub fn new(exposure: f64, operator: Operator) -> ToneMapping {
+        ToneMapping { exposure, operator }
+    }
+
+    /// Maps linear radiance to linear display values in `0..=1`.
+    pub fn apply(&self, color: Color) -> Color {
+        let scale = self.exposure.exp2();
+        Color::new(
+            self.operator.map(color.x * scale),
+            self.operator.map(color.y * scale),
+            self.operator.map(color.z * scale),
+        )
+    }
+
+    /// Maps linear radiance to 8-bit sRGB.
+    pub fn encode(&self, color: Color) -> [u8; 3] {
+        let color = self.apply(color);
+        [
+            quantize(srgb_transfer(color.x)),
+            quantize(srgb_transfer(color.y)),
+            quantize(srgb_transfer(color.z)),
+        ]
+    }
+}
+
+/// The sRGB opto-electronic transfer function for a linear value in `0..=1`.
+pub fn srgb_transfer(x: f64) -> f64 {
+    if x <= 0.003_130_8 {
+        12.92 * x
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse of `srgb_transfer`, decoding an sRGB value in `0..=1` to linear.
+pub fn srgb_to_linear(x: f64) -> f64 {
+    if x <= 0.040_45 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn quantize(x: f64) -> u8 {
+    (256.0 * x.clamp(0.0, 0.999)) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tonemap::{srgb_to_linear, srgb_transfer, Operator, ToneMapping};
+    use crate::vec3::Color;
+
+    const OPERATORS: [Operator; 5] = [
+        Operator::Clamp,
+        Operator::Reinhard,
+        Operator::ReinhardExtended { white_point: 4.0 },
+        Operator::Aces,
+        Operator::Hable,
+    ];
+
+    #[test]
+    fn operators_are_monotonic_and_bounded() {
+        for operator in OPERATORS {
+            assert_eq!(operator.map(0.0), 0.0, "{:?}", operator);
+            assert_eq!(operator.map(-1.0), 0.0, "{:?}", operator);
+
+            let mut previous = 0.0;
+            for i in 1..200 {
+                let mapped = operator.map(i as f64 * 0.1);
+                assert!(mapped >= previous, "{:?}", operator);
+                assert!(mapped <= 1.0, "{:?}", operator);
+                previous = mapped;
+            }
+        }
+    }
+
+    #[test]
+    fn operator_curves() {
+        assert_eq!(Operator::Clamp.map(3.0), 1.0);
+        assert_eq!(Operator::Reinhard.map(1.0), 0.5);
+        assert!(Operator::Reinhard.map(1000.0) < 1.0);
+        assert!((Operator::ReinhardExtended { white_point: 4.0 }.map(4.0) - 1.0).abs() < 1e-12);
+        assert!((Operator::Aces.map(0.18) - 0.2671).abs() < 1e-3);
+        assert!((Operator::Hable.map(5.6) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn operator_names() {
+        assert_eq!(Operator::from_name("ACES", 1.0), Some(Operator::Aces));
+        assert_eq!(
+            Operator::from_name("reinhard-extended", 2.0),
+            Some(Operator::ReinhardExtended { white_point: 2.0 })
+        );
+        assert_eq!(Operator::from_name("filmic", 1.0), None);
+    }
+
+    #[test]
+    fn srgb() {
+        assert_eq!(srgb_transfer(0.0), 0.0);
+        assert!((srgb_transfer(1.0) - 1.0).abs() < 1e-12);
+        assert!((srgb_transfer(0.002) - 0.02584).abs() < 1e-9);
+        assert!((srgb_transfer(0.5) - 0.7354).abs() < 1e-4);
+
+        for x in [0.0, 0.001, 0.2, 0.5, 1.0] {
+            assert!((srgb_to_linear(srgb_transfer(x)) - x).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn exposure() {
+        let tone_mapping = ToneMapping::new(1.0, Operator::Clamp);
+        assert_eq!(
+            tone_mapping.apply(Color::new(0.25, 0.5, 2.0)),
+            Color::new(0.5, 1.0, 1.0)
+        );
+
+        let encoded = ToneMapping::new(-1.0, Operator::Clamp).encode(Color::new(2.0, 1.0, 0.0));
+        assert_eq!(encoded, [255, 188, 0]);
+    }
+}
@@ -0,0 +1,421 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Color, Point3, Vec3};
+
+const EPSILON: f64 = 1e-9;
+
+pub struct Triangle {
+    pub v0: Point3,
+    pub v1: Point3,
+    pub v2: Point3,
+    pub material: Material,
+}
+
+impl Triangle {
+    pub fn new(v0: Point3, v1: Point3, v2: Point3, material: Material) -> Triangle {
+        Triangle {
+            v0,
+            v1,
+            v2,
+            material,
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (t, b1, b2) = intersect(r, self.v0, self.v1, self.v2, t_min, t_max)?;
+
+        let mut record = HitRecord::new_empty();
+        record.t = t;
+        record.p = r.at(t);
+        record.local = record.p;
+        record.barycentric = (b1, b2);
+        (record.u, record.v) = (b1, b2);
+        record.tangent = self.v1 - self.v0;
+        record.bitangent = self.v2 - self.v0;
+        let outward_normal = (self.v1 - self.v0).cross(self.v2 - self.v0).unit_vector();
+        record.set_face_normal(r, outward_normal);
+        record.material = &self.material;
+
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(triangle_box(self.v0, self.v1, self.v2))
+    }
+}
+
+/// Vertex and index buffers shared by every triangle of a mesh. Normals, UVs and
+/// colors are optional, but when present must have one entry per position.
+pub struct MeshData {
+    pub positions: Vec<Point3>,
+    pub indices: Vec<[usize; 3]>,
+    pub normals: Option<Vec<Vec3>>,
+    pub uvs: Option<Vec<(f64, f64)>>,
+    pub colors: Option<Vec<Color>>,
+    pub material: Material,
+}
+
+impl MeshData {
+    pub fn new(positions: Vec<Point3>, indices: Vec<[usize; 3]>, material: Material) -> MeshData {
+        MeshData {
+            positions,
+            indices,
+            normals: None,
+            uvs: None,
+            colors: None,
+            material,
+        }
+    }
+}
+
+/// Indexed triangle mesh. Each triangle references the shared `MeshData` and the
+/// mesh keeps its own BVH so it can be dropped into a scene as a single object.
+pub struct TriangleMesh {
+    data: Arc<MeshData>,
+    bvh: Bvh,
+}
+
+impl TriangleMesh {
+    pub fn new(data: MeshData) -> TriangleMesh {
+        let vertex_count = data.positions.len();
+        assert!(
+            data.indices.iter().flatten().all(|&i| i < vertex_count),
+            "mesh index out of range"
+        );
+        if let Some(normals) = &data.normals {
+            assert_eq!(normals.len(), vertex_count, "mesh normal count mismatch");
+        }
+        if let Some(uvs) = &data.uvs {
+            assert_eq!(uvs.len(), vertex_count, "mesh uv count mismatch");
+        }
+        if let Some(colors) = &data.colors {
+            assert_eq!(colors.len(), vertex_count, "mesh color count mismatch");
+        }
+
+        let data = Arc::new(data);
+        let triangles = (0..data.indices.len())
+            .map(|index| {
+                Arc::new(MeshTriangle {
+                    mesh: Arc::clone(&data),
+                    index,
+                }) as Arc<dyn Hittable>
+            })
+            .collect();
+
+        TriangleMesh {
+            data,
+            bvh: Bvh::new(triangles),
+        }
+    }
+
+    pub fn data(&self) -> &MeshData {
+        &self.data
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.data.indices.len()
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        self.bvh.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bvh.bounding_box()
+    }
+}
+
+struct MeshTriangle {
+    mesh: Arc<MeshData>,
+    index: usize,
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> (Point3, Point3, Point3) {
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+        let positions = &self.mesh.positions;
+        (positions[i0], positions[i1], positions[i2])
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        let (v0, v1, v2) = self.vertices();
+        let (t, b1, b2) = intersect(r, v0, v1, v2, t_min, t_max)?;
+
+        let mut record = HitRecord::new_empty();
+        record.t = t;
+        record.p = r.at(t);
+        record.local = record.p;
+        record.barycentric = (b1, b2);
+        let geometric_normal = (v1 - v0).cross(v2 - v0).unit_vector();
+        record.set_face_normal(r, geometric_normal);
+
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+        let b0 = 1.0 - b1 - b2;
+        (record.u, record.v) = match &self.mesh.uvs {
+            Some(uvs) => (
+                b0 * uvs[i0].0 + b1 * uvs[i1].0 + b2 * uvs[i2].0,
+                b0 * uvs[i0].1 + b1 * uvs[i1].1 + b2 * uvs[i2].1,
+            ),
+            None => (b1, b2),
+        };
+        record.color = self
+            .mesh
+            .colors
+            .as_ref()
+            .map(|colors| b0 * colors[i0] + b1 * colors[i1] + b2 * colors[i2]);
+        (record.tangent, record.bitangent) = (v1 - v0, v2 - v0);
+        if let Some(uvs) = &self.mesh.uvs {
+            // Solve the edges for the directions in which u and v grow.
+            let (du1, dv1) = (uvs[i1].0 - uvs[i0].0, uvs[i1].1 - uvs[i0].1);
+            let (du2, dv2) = (uvs[i2].0 - uvs[i0].0, uvs[i2].1 - uvs[i0].1);
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant.abs() > EPSILON {
+                let (e1, e2) = (v1 - v0, v2 - v0);
+                record.tangent = (dv2 * e1 - dv1 * e2) / determinant;
+                record.bitangent = (du1 * e2 - du2 * e1) / determinant;
+            }
+        }
+
+        if let Some(normals) = &self.mesh.normals {
+            let interpolated = (1.0 - b1 - b2) * normals[i0] + b1 * normals[i1] + b2 * normals[i2];
+            // Opposing vertex normals can cancel out; keep the geometric normal then.
+            if !interpolated.near_zero() {
+                let shading_normal = interpolated.unit_vector();
+                // Keep the interpolated normal on the same side as the geometric one so
+                // front_face stays consistent with the actual surface.
+                record.normal = if shading_normal.dot(record.normal) < 0.0 {
+                    -shading_normal
+                } else {
+                    shading_normal
+                };
+            }
+        }
+        record.material = &self.mesh.material;
+
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let (v0, v1, v2) = self.vertices();
+        Some(triangle_box(v0, v1, v2))
+    }
+}
+
+/// Möller–Trumbore ray/triangle intersection. Returns the ray parameter and the
+/// barycentric weights of `v1` and `v2`.
+fn intersect(
+    r: &Ray,
+    v0: Point3,
+    v1: Point3,
+    v2: Point3,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = r.direction.cross(edge2);
+    let determinant = edge1.dot(p);
+    if determinant.abs() < EPSILON {
+        return None;
+    }
+
+    let inverse_determinant = 1.0 / determinant;
+    let s = r.origin - v0;
+    let b1 = s.dot(p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let b2 = r.direction.dot(q) * inverse_determinant;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inverse_determinant;
+    if t < t_min || t_max < t {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+fn triangle_box(v0: Point3, v1: Point3, v2: Point3) -> Aabb {
+    let minimum = Point3::new(
+        v0.x.min(v1.x).min(v2.x),
+        v0.y.min(v1.y).min(v2.y),
+        v0.z.min(v1.z).min(v2.z),
+    );
+    let maximum = Point3::new(
+        v0.x.max(v1.x).max(v2.x),
+        v0.y.max(v1.y).max(v2.y),
+        v0.z.max(v1.z).max(v2.z),
+    );
+
+    Aabb::new(minimum, maximum)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hittable::Hittable;
+    use crate::material::Material;
+    use crate::ray::Ray;
+    use crate::triangle::{MeshData, Triangle, TriangleMesh};
+    use crate::vec3::{Color, Vec3};
+
+    fn unit_triangle() -> Triangle {
+        Triangle::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Material::None,
+        )
+    }
+
+    #[test]
+    fn hit_triangle() {
+        let triangle = unit_triangle();
+        let ray = Ray::new(Vec3::new(0.25, 0.5, 2.0), Vec3::new(0.0, 0.0, -1.0));
+
+        let record = triangle.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        assert_eq!(record.t, 2.0);
+        assert_eq!(record.p, Vec3::new(0.25, 0.5, 0.0));
+        assert_eq!(record.barycentric, (0.25, 0.5));
+        assert_eq!(record.normal, Vec3::new(0.0, 0.0, 1.0));
+        assert!(record.front_face);
+    }
+
+    #[test]
+    fn hit_triangle_back_face() {
+        let triangle = unit_triangle();
+        let ray = Ray::new(Vec3::new(0.25, 0.25, -2.0), Vec3::new(0.0, 0.0, 1.0));
+
+        let record = triangle.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        assert_eq!(record.normal, Vec3::new(0.0, 0.0, -1.0));
+        assert!(!record.front_face);
+    }
+
+    #[test]
+    fn miss_triangle() {
+        let triangle = unit_triangle();
+        let outside = Ray::new(Vec3::new(0.75, 0.75, 2.0), Vec3::new(0.0, 0.0, -1.0));
+        let parallel = Ray::new(Vec3::new(-1.0, 0.25, 0.0), Vec3::new(1.0, 0.0, 0.0));
+
+        assert!(triangle.hit(&outside, 0.001, f64::INFINITY).is_none());
+        assert!(triangle.hit(&parallel, 0.001, f64::INFINITY).is_none());
+    }
+
+    fn quad_mesh() -> MeshData {
+        MeshData::new(
+            vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+            Material::None,
+        )
+    }
+
+    #[test]
+    fn hit_triangle_mesh() {
+        let mesh = TriangleMesh::new(quad_mesh());
+        let ray = Ray::new(Vec3::new(0.25, 0.75, 1.0), Vec3::new(0.0, 0.0, -1.0));
+
+        let record = mesh.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(record.t, 1.0);
+        assert_eq!(record.normal, Vec3::new(0.0, 0.0, 1.0));
+
+        let bounding_box = mesh.bounding_box().unwrap();
+        assert_eq!(bounding_box.minimum, Vec3::new(0.0, 0.0, 0.0));
+        assert_eq!(bounding_box.maximum, Vec3::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn triangle_mesh_uvs() {
+        let ray = Ray::new(Vec3::new(0.25, 0.75, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut data = quad_mesh();
+        data.uvs = Some(vec![(0.0, 0.0), (0.5, 0.0), (0.5, 1.0), (0.0, 1.0)]);
+        let mesh = TriangleMesh::new(data);
+
+        let record = mesh.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!(record.color.is_none());
+        assert!((record.u - 0.125).abs() < 1e-9);
+        assert!((record.v - 0.75).abs() < 1e-9);
+
+        // u covers half the width, so it grows twice as fast along x.
+        assert!((record.tangent - Vec3::new(2.0, 0.0, 0.0)).near_zero());
+        assert!((record.bitangent - Vec3::new(0.0, 1.0, 0.0)).near_zero());
+    }
+
+    #[test]
+    fn triangle_mesh_colors() {
+        let ray = Ray::new(Vec3::new(0.25, 0.75, 1.0), Vec3::new(0.0, 0.0, -1.0));
+        let mut data = quad_mesh();
+        data.colors = Some(vec![
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.0, 0.0, 1.0),
+            Color::new(0.0, 0.0, 1.0),
+        ]);
+        let mesh = TriangleMesh::new(data);
+
+        // The hit lies in the second triangle, a quarter of the way to the red corner.
+        let record = mesh.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        let color = record.color.unwrap();
+        assert!((color - Color::new(0.25, 0.0, 0.75)).near_zero());
+    }
+
+    #[test]
+    fn triangle_mesh_smooth_normals() {
+        let mut data = quad_mesh();
+        let tilted = Vec3::new(1.0, 0.0, 1.0).unit_vector();
+        data.normals = Some(vec![tilted, tilted, tilted, tilted]);
+        let mesh = TriangleMesh::new(data);
+        let ray = Ray::new(Vec3::new(0.5, 0.25, -1.0), Vec3::new(0.0, 0.0, 1.0));
+
+        let record = mesh.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        assert!(!record.front_face);
+        assert!((record.normal + tilted).near_zero());
+        assert_eq!(record.geometric_normal, Vec3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn triangle_mesh_cancelling_normals() {
+        let mut data = quad_mesh();
+        let up = Vec3::new(0.0, 0.0, 1.0);
+        data.normals = Some(vec![-up, up, up, up]);
+        let mesh = TriangleMesh::new(data);
+        let ray = Ray::new(Vec3::new(0.5, 0.25, 1.0), Vec3::new(0.0, 0.0, -1.0));
+
+        let record = mesh.hit(&ray, 0.001, f64::INFINITY).unwrap();
+
+        assert_eq!(record.normal, record.geometric_normal);
+        assert_eq!(record.normal, up);
+    }
+
+    #[test]
+    #[should_panic]
+    fn triangle_mesh_invalid_index() {
+        let mut data = quad_mesh();
+        data.indices.push([0, 1, 4]);
+        TriangleMesh::new(data);
+    }
+}
@@ -1,6 +1,6 @@
 use std::{fmt, ops};
 
-use rand::{thread_rng, Rng};
+use crate::random::random_range;
 
 pub type Color = Vec3;
 pub type Point3 = Vec3;
@@ -17,7 +17,7 @@ impl Vec3 {
         Vec3 { x, y, z }
     }
 
-    fn length(&self) -> f64 {
+    pub fn length(&self) -> f64 {
         (self.length_squared()).sqrt()
     }
 
@@ -42,20 +42,23 @@ impl Vec3 {
     }
 
     pub fn random() -> Vec3 {
-        let mut rng = thread_rng();
-        Vec3 {
-            x: rng.gen_range(0.0..=1.0),
-            y: rng.gen_range(0.0..=1.0),
-            z: rng.gen_range(0.0..=1.0),
-        }
+        Vec3::random_range(0.0, 1.0)
     }
 
     pub fn random_range(min: f64, max: f64) -> Vec3 {
-        let mut rng = thread_rng();
         Vec3 {
-            x: rng.gen_range(min..=max),
-            y: rng.gen_range(min..=max),
-            z: rng.gen_range(min..=max),
+            x: random_range(min, max),
+            y: random_range(min, max),
+            z: random_range(min, max),
+        }
+    }
+
+    pub fn random_in_unit_disk() -> Vec3 {
+        loop {
+            let p = Vec3::new(random_range(-1.0, 1.0), random_range(-1.0, 1.0), 0.0);
+            if p.length_squared() < 1.0 {
+                return p;
+            }
         }
     }
 
@@ -389,6 +392,15 @@ mod tests {
         assert_eq!(result, 38.0);
     }
 
+    #[test]
+    fn random_in_unit_disk() {
+        for _ in 0..100 {
+            let p = Vec3::random_in_unit_disk();
+            assert!(p.length_squared() < 1.0);
+            assert_eq!(p.z, 0.0);
+        }
+    }
+
     #[test]
     fn equality() {
         let test1 = Vec3::new(5.0, 6.0, 7.0);